@@ -0,0 +1,631 @@
+//! Archive Intake
+//!
+//! Governance: .agent/skills/architectural_guidelines/SKILL.md (Level 3: Processing Pipeline)
+//!
+//! Replaces the legacy `ArchiveProjectPlugin` (`patoolib` + external CLIs).
+//! Archives are never extracted as a whole into memory: entries are iterated
+//! one at a time and each one is streamed through a small buffer into a working
+//! directory, which the pipeline then processes like a dropped folder.
+//!
+//! Each container format is handled by an [`Extractor`] backend. ZIP and TAR
+//! (plain or compressed with gzip, xz or zstd) are always available; 7-Zip
+//! and RAR are optional backends behind the `archive-7z` and `archive-rar`
+//! cargo features. The format is chosen by file name. Encrypted archives are
+//! rejected up front with [`ArchiveError::PasswordRequired`] instead of
+//! waiting for a password that never comes.
+//!
+//! Extraction lands on the tmpfs working dir, so every archive is treated as
+//! hostile: [`ArchiveLimits`] caps the total output, the compression ratio,
+//! the entry count and the path depth, entry names must stay inside the
+//! destination, and link entries are refused. Each violation is its own
+//! [`ArchiveError`] variant, so jobs fail with a distinct reason.
+//!
+//! Downloads often wrap per-variant archives (`supported.zip`,
+//! `32mm.zip`) in an outer one. Archives found in the extracted tree are
+//! unpacked in place, up to [`ArchiveLimits::max_nesting`] levels, and
+//! [`Extraction::nested`] records which archive each folder came from.
+
+#[cfg(feature = "archive-rar")]
+mod rar;
+#[cfg(feature = "archive-7z")]
+mod sevenz;
+mod tar;
+mod zip;
+
+use crate::geometry::ProgressSink;
+use crate::progress::Stage;
+use anyhow::Context;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Copy buffer for streaming entries to disk.
+const COPY_BUFFER: usize = 64 * 1024;
+/// Output size below which the compression ratio is not enforced.
+const RATIO_FLOOR: u64 = 16 * 1024 * 1024;
+
+/// Container/compression combination of an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+    SevenZip,
+    Rar,
+}
+
+impl ArchiveFormat {
+    /// Detects the format from the file name (`.zip`, `.tar`, `.tar.gz` /
+    /// `.tgz`, `.tar.xz` / `.txz`, `.tar.zst` / `.tzst`, `.7z`, `.rar`).
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        let suffixes = [
+            (".zip", ArchiveFormat::Zip),
+            (".tar", ArchiveFormat::Tar),
+            (".tar.gz", ArchiveFormat::TarGz),
+            (".tgz", ArchiveFormat::TarGz),
+            (".tar.xz", ArchiveFormat::TarXz),
+            (".txz", ArchiveFormat::TarXz),
+            (".tar.zst", ArchiveFormat::TarZst),
+            (".tzst", ArchiveFormat::TarZst),
+            (".7z", ArchiveFormat::SevenZip),
+            (".rar", ArchiveFormat::Rar),
+        ];
+        suffixes
+            .into_iter()
+            .find(|(suffix, _)| name.ends_with(suffix))
+            .map(|(_, format)| format)
+    }
+
+    /// The archive's file name without the format suffix, e.g. `dragon` for
+    /// `dragon.tar.gz`.
+    pub fn stem(self, path: &Path) -> String {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let lower = name.to_ascii_lowercase();
+        let suffix_len = [".tar.gz", ".tar.xz", ".tar.zst"]
+            .iter()
+            .find(|suffix| lower.ends_with(*suffix))
+            .map_or_else(
+                || lower.rfind('.').map_or(0, |dot| lower.len() - dot),
+                |suffix| suffix.len(),
+            );
+        name[..name.len() - suffix_len].to_string()
+    }
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ArchiveFormat::Zip => "ZIP",
+            ArchiveFormat::Tar => "TAR",
+            ArchiveFormat::TarGz => "TAR (gzip)",
+            ArchiveFormat::TarXz => "TAR (xz)",
+            ArchiveFormat::TarZst => "TAR (zstd)",
+            ArchiveFormat::SevenZip => "7-Zip",
+            ArchiveFormat::Rar => "RAR",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Why an archive could not be extracted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArchiveError {
+    /// The file name does not map to a supported archive format.
+    Unsupported { name: String },
+    /// The format is known but its extractor was not compiled in.
+    #[cfg_attr(all(feature = "archive-7z", feature = "archive-rar"), allow(dead_code))]
+    FeatureDisabled {
+        format: ArchiveFormat,
+        feature: &'static str,
+    },
+    /// Entries (or the archive headers) are encrypted.
+    PasswordRequired,
+    /// The container or its compression stream is damaged.
+    Corrupt(String),
+    /// Extracting would write more than the total size limit (bytes).
+    TooLarge { limit: u64 },
+    /// Output grows more than `limit` times the archive size.
+    RatioExceeded { ratio: u64, limit: u64 },
+    /// More entries than the limit.
+    TooManyEntries { limit: u64 },
+    /// An entry path has more components than the limit.
+    TooDeep { entry: String, limit: usize },
+    /// An entry name is absolute or climbs out of the destination (`..`).
+    UnsafePath { entry: String },
+    /// A symbolic or hard link entry.
+    Link { entry: String },
+}
+
+impl ArchiveError {
+    /// Stable identifier recorded as the job's failure reason.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ArchiveError::Unsupported { .. } => "archive_unsupported",
+            ArchiveError::FeatureDisabled { .. } => "archive_format_disabled",
+            ArchiveError::PasswordRequired => "archive_password_required",
+            ArchiveError::Corrupt(_) => "archive_corrupt",
+            ArchiveError::TooLarge { .. } => "archive_too_large",
+            ArchiveError::RatioExceeded { .. } => "archive_ratio_exceeded",
+            ArchiveError::TooManyEntries { .. } => "archive_too_many_entries",
+            ArchiveError::TooDeep { .. } => "archive_too_deep",
+            ArchiveError::UnsafePath { .. } => "archive_unsafe_path",
+            ArchiveError::Link { .. } => "archive_link",
+        }
+    }
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Unsupported { name } => {
+                write!(f, "{:?} is not a supported archive format", name)
+            }
+            ArchiveError::FeatureDisabled { format, feature } => write!(
+                f,
+                "{} archives are not supported by this build (cargo feature `{}`)",
+                format, feature
+            ),
+            ArchiveError::PasswordRequired => {
+                write!(f, "password required: the archive is encrypted")
+            }
+            ArchiveError::Corrupt(message) => write!(f, "archive is damaged: {}", message),
+            ArchiveError::TooLarge { limit } => write!(
+                f,
+                "archive expands to more than {} MiB",
+                limit / (1024 * 1024)
+            ),
+            ArchiveError::RatioExceeded { ratio, limit } => write!(
+                f,
+                "compression ratio {}:1 exceeds the limit of {}:1 (zip bomb?)",
+                ratio, limit
+            ),
+            ArchiveError::TooManyEntries { limit } => {
+                write!(f, "archive has more than {} entries", limit)
+            }
+            ArchiveError::TooDeep { entry, limit } => write!(
+                f,
+                "entry {:?} is nested more than {} directories deep",
+                entry, limit
+            ),
+            ArchiveError::UnsafePath { entry } => write!(
+                f,
+                "entry {:?} would be written outside the extraction folder",
+                entry
+            ),
+            ArchiveError::Link { entry } => {
+                write!(f, "entry {:?} is a link; links are not extracted", entry)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+/// Result of [`extract`].
+#[derive(Debug, Clone)]
+pub struct Extraction {
+    pub format: ArchiveFormat,
+    /// Regular files written.
+    pub files: u64,
+    /// Uncompressed bytes written.
+    pub bytes: u64,
+    /// Entries that were not extracted (device files, FIFOs).
+    pub skipped: Vec<String>,
+    /// Inner archives that were unpacked in place, outermost first.
+    pub nested: Vec<NestedArchive>,
+    /// Inner archives kept as files (nesting limit reached or format not
+    /// compiled in), relative to the destination.
+    pub left_packed: Vec<PathBuf>,
+}
+
+impl Extraction {
+    /// The innermost nested archive a file (relative to the destination)
+    /// came from, or `None` if it came straight from the outer archive.
+    pub fn origin(&self, file: &Path) -> Option<&NestedArchive> {
+        self.nested
+            .iter()
+            .filter(|nested| file.starts_with(&nested.folder))
+            .max_by_key(|nested| nested.chain.len())
+    }
+}
+
+/// An archive found inside the extracted tree and unpacked into a folder
+/// named after it, next to where it was, so variant archives
+/// (`supported.zip`, `unsupported.zip`) become variant folders.
+#[derive(Debug, Clone)]
+pub struct NestedArchive {
+    /// Where the archive was, relative to the destination.
+    pub archive: PathBuf,
+    /// Folder it was unpacked into, relative to the destination.
+    pub folder: PathBuf,
+    /// File names of the enclosing archives, outermost first, ending with
+    /// this archive's own name.
+    pub chain: Vec<String>,
+    /// Regular files it contained (including archives unpacked further).
+    pub files: u64,
+}
+
+/// Safety limits applied while extracting.
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveLimits {
+    /// Total uncompressed bytes.
+    pub max_total_bytes: u64,
+    /// Uncompressed bytes per byte of archive.
+    pub max_ratio: u64,
+    /// Entries of any kind (files, directories, skipped entries).
+    pub max_entries: u64,
+    /// Path components of an entry name.
+    pub max_depth: usize,
+    /// Levels of archives inside the archive that are unpacked as well; 0
+    /// leaves inner archives packed.
+    pub max_nesting: u32,
+}
+
+/// A container format backend.
+trait Extractor {
+    /// Streams every entry of `archive` into `unpacker`. Encrypted content
+    /// fails with [`ArchiveError::PasswordRequired`].
+    fn extract(
+        &self,
+        archive: &Path,
+        unpacker: &mut Unpacker,
+        progress: &dyn ProgressSink,
+    ) -> anyhow::Result<()>;
+}
+
+/// Selects the backend for `format`.
+fn extractor(format: ArchiveFormat) -> Result<Box<dyn Extractor>, ArchiveError> {
+    match format {
+        ArchiveFormat::Zip => Ok(Box::new(zip::ZipExtractor)),
+        ArchiveFormat::Tar
+        | ArchiveFormat::TarGz
+        | ArchiveFormat::TarXz
+        | ArchiveFormat::TarZst => Ok(Box::new(tar::TarExtractor { format })),
+        #[cfg(feature = "archive-7z")]
+        ArchiveFormat::SevenZip => Ok(Box::new(sevenz::SevenZipExtractor)),
+        #[cfg(not(feature = "archive-7z"))]
+        ArchiveFormat::SevenZip => Err(ArchiveError::FeatureDisabled {
+            format,
+            feature: "archive-7z",
+        }),
+        #[cfg(feature = "archive-rar")]
+        ArchiveFormat::Rar => Ok(Box::new(rar::RarExtractor)),
+        #[cfg(not(feature = "archive-rar"))]
+        ArchiveFormat::Rar => Err(ArchiveError::FeatureDisabled {
+            format,
+            feature: "archive-rar",
+        }),
+    }
+}
+
+/// Extracts `archive` into the existing directory `dest`, one entry at a time.
+/// The first violated limit aborts the extraction; files written so far are
+/// left for the caller to clean up.
+pub fn extract(
+    archive: &Path,
+    dest: &Path,
+    limits: ArchiveLimits,
+    progress: &dyn ProgressSink,
+) -> anyhow::Result<Extraction> {
+    let format = ArchiveFormat::detect(archive).ok_or_else(|| ArchiveError::Unsupported {
+        name: archive
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    })?;
+    let backend = extractor(format)?;
+    log::info!(
+        "Archive: extracting {:?} ({}) to {:?}",
+        archive,
+        format,
+        dest
+    );
+
+    let mut unpacker = Unpacker::new(dest, fs::metadata(archive)?.len(), limits);
+    progress.progress(Stage::Extracting, 0.0);
+    backend.extract(archive, &mut unpacker, progress)?;
+
+    // Inner archives, breadth first; size and entry limits apply to the
+    // whole tree, the ratio to each archive on its own.
+    let outer_name = file_name(archive);
+    let mut nested: Vec<NestedArchive> = Vec::new();
+    let mut left_packed = Vec::new();
+    let mut pending: VecDeque<(PathBuf, Vec<String>, u32)> = unpacker
+        .archives
+        .drain(..)
+        .map(|inner| (inner, vec![outer_name.clone()], 1))
+        .collect();
+    while let Some((inner, parents, level)) = pending.pop_front() {
+        progress.check_cancelled()?;
+        let relative = inner.strip_prefix(dest).unwrap_or(&inner).to_path_buf();
+        let Some(format) = ArchiveFormat::detect(&inner) else {
+            continue;
+        };
+        let backend = match extractor(format) {
+            Ok(backend) if level <= limits.max_nesting => backend,
+            Ok(_) => {
+                log::warn!("Archive: nesting limit reached, keeping {:?}", relative);
+                left_packed.push(relative);
+                continue;
+            }
+            Err(e) => {
+                log::warn!("Archive: keeping {:?}: {}", relative, e);
+                left_packed.push(relative);
+                continue;
+            }
+        };
+
+        let folder = unique_folder(&inner.with_file_name(format.stem(&inner)));
+        fs::create_dir_all(&folder)?;
+        log::info!("Archive: unpacking nested {:?} into {:?}", inner, folder);
+        let files_before = unpacker.files;
+        unpacker.enter(&folder, fs::metadata(&inner)?.len());
+        backend
+            .extract(&inner, &mut unpacker, progress)
+            .with_context(|| format!("in nested archive {:?}", relative))?;
+        let files = unpacker.files - files_before;
+        fs::remove_file(&inner)?;
+        unpacker.files -= 1;
+        hoist_wrapper(&folder, &mut unpacker.archives)?;
+
+        let mut chain = parents;
+        chain.push(file_name(&inner));
+        for deeper in unpacker.archives.drain(..) {
+            pending.push_back((deeper, chain.clone(), level + 1));
+        }
+        // Files of deeper archives also count towards their parents.
+        for parent in nested
+            .iter_mut()
+            .filter(|parent| relative.starts_with(&parent.folder))
+        {
+            parent.files = parent.files + files - 1;
+        }
+        nested.push(NestedArchive {
+            archive: relative,
+            folder: folder.strip_prefix(dest).unwrap_or(&folder).to_path_buf(),
+            chain,
+            files,
+        });
+    }
+    progress.progress(Stage::Extracting, 1.0);
+
+    Ok(Extraction {
+        format,
+        files: unpacker.files,
+        bytes: unpacker.bytes,
+        skipped: unpacker.skipped,
+        nested,
+        left_packed,
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Archives often wrap their content in a folder named like the archive
+/// (`32mm.zip` holding `32mm/`); that level is removed so the variant folder
+/// does not repeat. `archives` found inside are moved along.
+fn hoist_wrapper(folder: &Path, archives: &mut [PathBuf]) -> io::Result<()> {
+    let entries: Vec<fs::DirEntry> = fs::read_dir(folder)?.collect::<Result<_, _>>()?;
+    let [wrapper] = entries.as_slice() else {
+        return Ok(());
+    };
+    let same_name =
+        wrapper.file_name().to_string_lossy().to_lowercase() == file_name(folder).to_lowercase();
+    if !same_name || !wrapper.file_type()?.is_dir() {
+        return Ok(());
+    }
+    let wrapper = wrapper.path();
+    let staging = folder.with_file_name(format!(".{}.hoist", file_name(folder)));
+    fs::rename(&wrapper, &staging)?;
+    fs::remove_dir(folder)?;
+    fs::rename(&staging, folder)?;
+    for archive in archives.iter_mut() {
+        if let Ok(rest) = archive.strip_prefix(&wrapper) {
+            *archive = folder.join(rest);
+        }
+    }
+    Ok(())
+}
+
+/// `path`, or `path (2)`, `path (3)`, ... if it already exists.
+fn unique_folder(path: &Path) -> PathBuf {
+    let mut candidate = path.to_path_buf();
+    let mut n = 2;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!("{} ({})", file_name(path), n));
+        n += 1;
+    }
+    candidate
+}
+
+/// Writes archive entries below a destination directory and keeps count.
+struct Unpacker {
+    dest: PathBuf,
+    limits: ArchiveLimits,
+    /// Size of the archive being extracted, the base of the compression ratio.
+    archive_size: u64,
+    /// `bytes` when the current archive was entered.
+    archive_start: u64,
+    entries: u64,
+    files: u64,
+    bytes: u64,
+    skipped: Vec<String>,
+    /// Extracted files that are archives themselves.
+    archives: Vec<PathBuf>,
+}
+
+impl Unpacker {
+    fn new(dest: &Path, archive_size: u64, limits: ArchiveLimits) -> Self {
+        Self {
+            dest: dest.to_path_buf(),
+            limits,
+            archive_size,
+            archive_start: 0,
+            entries: 0,
+            files: 0,
+            bytes: 0,
+            skipped: Vec::new(),
+            archives: Vec::new(),
+        }
+    }
+
+    /// Continues with a nested archive of `archive_size` bytes, extracted
+    /// into `dest`. Counts and totals carry over.
+    fn enter(&mut self, dest: &Path, archive_size: u64) {
+        self.dest = dest.to_path_buf();
+        self.archive_size = archive_size;
+        self.archive_start = self.bytes;
+    }
+
+    /// Streams one regular file entry to disk. Read errors mean the archive
+    /// is damaged; write errors are passed through as I/O errors. `declared`
+    /// is the size from the entry header; the limits are enforced again on
+    /// the bytes actually written, since headers can lie.
+    fn file(&mut self, name: &str, declared: u64, reader: &mut dyn Read) -> anyhow::Result<()> {
+        let Some(target) = self.reserve(name, declared)? else {
+            return Ok(());
+        };
+        let mut out = BufWriter::new(File::create(&target)?);
+        let mut buf = vec![0u8; COPY_BUFFER];
+        loop {
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(ArchiveError::Corrupt(format!("{}: {}", name, e)).into()),
+            };
+            self.bytes += read as u64;
+            self.check_size(self.bytes)?;
+            out.write_all(&buf[..read])?;
+        }
+        out.flush()?;
+        self.files += 1;
+        Ok(())
+    }
+
+    /// Target path of a regular file entry of `declared` bytes with its
+    /// parent directories created, or `None` if the entry has no name.
+    /// Backends that write files themselves report the size with
+    /// [`Unpacker::written`] afterwards.
+    fn reserve(&mut self, name: &str, declared: u64) -> anyhow::Result<Option<PathBuf>> {
+        let Some(target) = self.target(name)? else {
+            return Ok(None);
+        };
+        self.check_size(self.bytes.saturating_add(declared))?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if ArchiveFormat::detect(&target).is_some() {
+            self.archives.push(target.clone());
+        }
+        Ok(Some(target))
+    }
+
+    /// Counts one file of `bytes` bytes written by the backend.
+    #[cfg_attr(not(feature = "archive-rar"), allow(dead_code))]
+    fn written(&mut self, bytes: u64) -> Result<(), ArchiveError> {
+        self.bytes += bytes;
+        self.files += 1;
+        self.check_size(self.bytes)
+    }
+
+    /// Creates a directory entry (empty directories are kept).
+    fn dir(&mut self, name: &str) -> anyhow::Result<()> {
+        if let Some(target) = self.target(name)? {
+            fs::create_dir_all(target)?;
+        }
+        Ok(())
+    }
+
+    /// Records an entry that is not extracted (device files, FIFOs).
+    fn skip(&mut self, name: &str) -> Result<(), ArchiveError> {
+        self.count_entry()?;
+        log::warn!("Archive: skipping {:?}", name);
+        self.skipped.push(name.to_string());
+        Ok(())
+    }
+
+    /// Maps an entry name to a path below `dest`, or `None` for an empty
+    /// name. Absolute names, `..` components and paths nested deeper than
+    /// the limit are rejected.
+    fn target(&mut self, name: &str) -> Result<Option<PathBuf>, ArchiveError> {
+        self.count_entry()?;
+        let unsafe_path = || ArchiveError::UnsafePath {
+            entry: name.to_string(),
+        };
+        if name.contains('\0') {
+            return Err(unsafe_path());
+        }
+        let mut relative = PathBuf::new();
+        let mut depth = 0;
+        for component in Path::new(&name.replace('\\', "/")).components() {
+            match component {
+                // `C:` drive prefixes are plain components on Unix.
+                Component::Normal(part) if part.to_string_lossy().ends_with(':') => {
+                    return Err(unsafe_path());
+                }
+                Component::Normal(part) => {
+                    relative.push(part);
+                    depth += 1;
+                }
+                Component::CurDir => {}
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    return Err(unsafe_path());
+                }
+            }
+        }
+        if depth > self.limits.max_depth {
+            return Err(ArchiveError::TooDeep {
+                entry: name.to_string(),
+                limit: self.limits.max_depth,
+            });
+        }
+        if relative.as_os_str().is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(self.dest.join(relative)))
+    }
+
+    fn count_entry(&mut self) -> Result<(), ArchiveError> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(ArchiveError::TooManyEntries {
+                limit: self.limits.max_entries,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks an (expected) total of `bytes` uncompressed bytes against the
+    /// size and ratio limits.
+    fn check_size(&self, bytes: u64) -> Result<(), ArchiveError> {
+        if bytes > self.limits.max_total_bytes {
+            return Err(ArchiveError::TooLarge {
+                limit: self.limits.max_total_bytes,
+            });
+        }
+        // Small archives of highly redundant data legitimately reach high
+        // ratios; only sizable outputs are held to the limit.
+        let own = bytes - self.archive_start;
+        let ratio = own / self.archive_size.max(1);
+        if own > RATIO_FLOOR && ratio > self.limits.max_ratio {
+            return Err(ArchiveError::RatioExceeded {
+                ratio,
+                limit: self.limits.max_ratio,
+            });
+        }
+        Ok(())
+    }
+}
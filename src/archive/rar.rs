@@ -0,0 +1,85 @@
+//! RAR entries, extracted by the bundled UnRAR library.
+//!
+//! UnRAR writes each entry to disk itself, so the limits cannot be checked
+//! while the bytes stream. Instead the entry's declared size is checked
+//! before extraction: UnRAR never writes more than the header's unpacked
+//! size (entries of unknown size report about 2^63 bytes and fail the
+//! check), so the declared size is a hard upper bound of the output.
+
+use super::{ArchiveError, Extractor, Unpacker};
+use crate::geometry::ProgressSink;
+use crate::progress::Stage;
+use std::path::Path;
+use unrar::error::{Code, UnrarError};
+
+pub(super) struct RarExtractor;
+
+impl Extractor for RarExtractor {
+    fn extract(
+        &self,
+        archive: &Path,
+        unpacker: &mut Unpacker,
+        progress: &dyn ProgressSink,
+    ) -> anyhow::Result<()> {
+        // List first: UnRAR would otherwise ask for a password mid-way.
+        // Encrypted headers already fail to open with `MissingPassword`.
+        let listing = unrar::Archive::new(archive)
+            .open_for_listing()
+            .map_err(archive_error)?;
+        let mut count = 0usize;
+        for header in listing {
+            if header.map_err(archive_error)?.is_encrypted() {
+                return Err(ArchiveError::PasswordRequired.into());
+            }
+            count += 1;
+        }
+
+        let mut open = unrar::Archive::new(archive)
+            .open_for_processing()
+            .map_err(archive_error)?;
+        let mut index = 0usize;
+        while let Some(cursor) = open.read_header().map_err(archive_error)? {
+            progress.progress(Stage::Extracting, index as f64 / count.max(1) as f64);
+            progress.check_cancelled()?;
+            index += 1;
+            let entry = cursor.entry();
+            let name = entry.filename.to_string_lossy().into_owned();
+            open = if is_link(entry.file_attr) {
+                return Err(ArchiveError::Link { entry: name }.into());
+            } else if entry.is_directory() {
+                unpacker.dir(&name)?;
+                cursor.skip().map_err(archive_error)?
+            } else if let Some(target) = unpacker.reserve(&name, entry.unpacked_size)? {
+                let declared = entry.unpacked_size;
+                let next = cursor.extract_to(&target).map_err(archive_error)?;
+                let written = std::fs::metadata(&target)?.len();
+                if written > declared {
+                    std::fs::remove_file(&target)?;
+                    return Err(ArchiveError::Corrupt(format!(
+                        "{}: {} bytes written, header declares {}",
+                        name, written, declared
+                    ))
+                    .into());
+                }
+                unpacker.written(written)?;
+                next
+            } else {
+                cursor.skip().map_err(archive_error)?
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Unix symlink mode, or a Windows reparse point (symlink or junction).
+fn is_link(attributes: u32) -> bool {
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+    attributes & 0o170000 == 0o120000 || attributes & FILE_ATTRIBUTE_REPARSE_POINT != 0
+}
+
+fn archive_error(error: UnrarError) -> ArchiveError {
+    match error.code {
+        Code::MissingPassword | Code::BadPassword => ArchiveError::PasswordRequired,
+        _ => ArchiveError::Corrupt(error.to_string()),
+    }
+}
@@ -0,0 +1,87 @@
+//! 7-Zip entries, decoded block by block with `sevenz-rust2`.
+
+use super::{ArchiveError, Extractor, Unpacker};
+use crate::geometry::ProgressSink;
+use crate::progress::Stage;
+use sevenz_rust2::{ArchiveEntry, ArchiveReader, EncoderMethod, Password};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+pub(super) struct SevenZipExtractor;
+
+impl Extractor for SevenZipExtractor {
+    fn extract(
+        &self,
+        archive: &Path,
+        unpacker: &mut Unpacker,
+        progress: &dyn ProgressSink,
+    ) -> anyhow::Result<()> {
+        let file = File::open(archive)?;
+        // Encrypted headers already fail here with `PasswordRequired`.
+        let mut reader =
+            ArchiveReader::new(BufReader::new(file), Password::empty()).map_err(archive_error)?;
+        let encrypted = reader
+            .archive()
+            .blocks
+            .iter()
+            .flat_map(|block| &block.coders)
+            .any(|coder| coder.encoder_method_id() == EncoderMethod::ID_AES256_SHA256);
+        if encrypted {
+            return Err(ArchiveError::PasswordRequired.into());
+        }
+
+        let count = reader.archive().files.len().max(1);
+        let mut index = 0usize;
+        let mut failure: Option<anyhow::Error> = None;
+        reader
+            .for_each_entries(|entry, data| {
+                if failure.is_some() {
+                    return Ok(false);
+                }
+                progress.progress(Stage::Extracting, index as f64 / count as f64);
+                index += 1;
+                let result = progress.check_cancelled().and_then(|()| {
+                    if is_symlink(entry) {
+                        Err(ArchiveError::Link {
+                            entry: entry.name().to_string(),
+                        }
+                        .into())
+                    } else if entry.is_directory() {
+                        unpacker.dir(entry.name())
+                    } else if entry.is_anti_item() {
+                        Ok(unpacker.skip(entry.name())?)
+                    } else {
+                        unpacker.file(entry.name(), entry.size(), data)
+                    }
+                });
+                match result {
+                    Ok(()) => Ok(true),
+                    Err(e) => {
+                        failure = Some(e);
+                        Ok(false)
+                    }
+                }
+            })
+            .map_err(archive_error)?;
+        failure.map_or(Ok(()), Err)
+    }
+}
+
+/// 7-Zip stores Unix modes in the upper 16 bits of the attributes, flagged by
+/// `0x8000`.
+fn is_symlink(entry: &ArchiveEntry) -> bool {
+    let attributes = entry.windows_attributes();
+    entry.has_windows_attributes
+        && attributes & 0x8000 != 0
+        && (attributes >> 16) & 0o170000 == 0o120000
+}
+
+fn archive_error(error: sevenz_rust2::Error) -> ArchiveError {
+    match error {
+        sevenz_rust2::Error::PasswordRequired | sevenz_rust2::Error::MaybeBadPassword(_) => {
+            ArchiveError::PasswordRequired
+        }
+        other => ArchiveError::Corrupt(other.to_string()),
+    }
+}
@@ -0,0 +1,79 @@
+//! TAR entries, read sequentially through the decompressor.
+
+use super::{ArchiveError, ArchiveFormat, Extractor, Unpacker};
+use crate::geometry::ProgressSink;
+use crate::progress::Stage;
+use ::tar::EntryType;
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Plain or compressed TAR; `format` selects the decompressor.
+pub(super) struct TarExtractor {
+    pub format: ArchiveFormat,
+}
+
+impl Extractor for TarExtractor {
+    fn extract(
+        &self,
+        archive: &Path,
+        unpacker: &mut Unpacker,
+        progress: &dyn ProgressSink,
+    ) -> anyhow::Result<()> {
+        let file = File::open(archive)?;
+        let len = file.metadata()?.len().max(1);
+        let consumed = Rc::new(Cell::new(0u64));
+        let raw = BufReader::new(Counting {
+            inner: file,
+            consumed: Rc::clone(&consumed),
+        });
+        let stream: Box<dyn Read> = match self.format {
+            ArchiveFormat::TarGz => Box::new(flate2::read::MultiGzDecoder::new(raw)),
+            ArchiveFormat::TarXz => Box::new(xz2::read::XzDecoder::new_multi_decoder(raw)),
+            ArchiveFormat::TarZst => Box::new(zstd::Decoder::with_buffer(raw)?),
+            _ => Box::new(raw),
+        };
+
+        let corrupt = |e: io::Error| ArchiveError::Corrupt(e.to_string());
+        let mut tar = ::tar::Archive::new(stream);
+        for entry in tar.entries().map_err(corrupt)? {
+            progress.progress(Stage::Extracting, consumed.get() as f64 / len as f64);
+            progress.check_cancelled()?;
+            let mut entry = entry.map_err(corrupt)?;
+            let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+            match entry.header().entry_type() {
+                EntryType::Regular | EntryType::Continuous => {
+                    let size = entry.size();
+                    unpacker.file(&name, size, &mut entry)?
+                }
+                EntryType::Directory => unpacker.dir(&name)?,
+                EntryType::Symlink | EntryType::Link => {
+                    return Err(ArchiveError::Link { entry: name }.into());
+                }
+                // Extended headers are applied to the next entry by the tar crate.
+                EntryType::XGlobalHeader
+                | EntryType::XHeader
+                | EntryType::GNULongName
+                | EntryType::GNULongLink => {}
+                _ => unpacker.skip(&name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counts compressed bytes read from the archive file for progress.
+struct Counting {
+    inner: File,
+    consumed: Rc<Cell<u64>>,
+}
+
+impl Read for Counting {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.consumed.set(self.consumed.get() + read as u64);
+        Ok(read)
+    }
+}
@@ -0,0 +1,55 @@
+//! ZIP entries, read through the central directory.
+
+use super::{ArchiveError, Extractor, Unpacker};
+use crate::geometry::ProgressSink;
+use crate::progress::Stage;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+pub(super) struct ZipExtractor;
+
+impl Extractor for ZipExtractor {
+    fn extract(
+        &self,
+        archive: &Path,
+        unpacker: &mut Unpacker,
+        progress: &dyn ProgressSink,
+    ) -> anyhow::Result<()> {
+        let file = File::open(archive)?;
+        let mut zip = ::zip::ZipArchive::new(BufReader::new(file))
+            .map_err(|e| ArchiveError::Corrupt(e.to_string()))?;
+        let count = zip.len().max(1);
+
+        // Checked before writing anything so a half-encrypted archive does
+        // not leave a partial tree behind.
+        for index in 0..zip.len() {
+            let entry = zip
+                .by_index_raw(index)
+                .map_err(|e| ArchiveError::Corrupt(e.to_string()))?;
+            if entry.encrypted() {
+                return Err(ArchiveError::PasswordRequired.into());
+            }
+        }
+
+        for index in 0..zip.len() {
+            progress.progress(Stage::Extracting, index as f64 / count as f64);
+            progress.check_cancelled()?;
+            let mut entry = zip
+                .by_index(index)
+                .map_err(|e| ArchiveError::Corrupt(e.to_string()))?;
+            let name = entry.name().to_string();
+            if entry.is_symlink() {
+                return Err(ArchiveError::Link { entry: name }.into());
+            } else if entry.is_dir() {
+                unpacker.dir(&name)?;
+            } else if entry.is_file() {
+                let size = entry.size();
+                unpacker.file(&name, size, &mut entry)?;
+            } else {
+                unpacker.skip(&name)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+//! Runtime Configuration
+//!
+//! Governance: .agent/skills/environment_constraints/SKILL.md
+//!
+//! All values are read from environment variables once at startup. Defaults
+//! match the volume layout in `compose.yml` and `.agent/constants.yml`.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Process-wide configuration resolved from the environment.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Watched intake volume (`INPUT_DIR`, default `/input`).
+    pub input_dir: PathBuf,
+    /// Destination for processed model folders (`OUTPUT_DIR`, default `/output`).
+    pub output_dir: PathBuf,
+    /// Persistent state such as the job queue (`CONFIG_DIR`, default `/config`).
+    pub config_dir: PathBuf,
+    /// Port of the dashboard and API (`WEB_PORT`, default 8080).
+    pub web_port: u16,
+    /// Quiet period after the last filesystem event before an item is checked
+    /// for stability (`WATCHER_SETTLE_SECS`, default 5).
+    pub settle_delay: Duration,
+    /// Interval between size checks while waiting for a copy to finish
+    /// (`WATCHER_POLL_SECS`, default 2).
+    pub poll_interval: Duration,
+    /// Use a polling backend instead of inotify (`WATCHER_POLLING`). Needed for
+    /// SMB/NFS and Docker Desktop bind mounts, which do not deliver events.
+    pub watcher_polling: bool,
+    /// How often a job may be started before it is failed permanently
+    /// (`JOB_MAX_ATTEMPTS`, default 3). Protects against crash loops on inputs
+    /// that OOM-kill the container.
+    pub max_attempts: u32,
+    /// Finished jobs kept in the queue (`JOB_RETENTION`, default 500); older
+    /// ones are pruned, their output stays.
+    pub job_retention: usize,
+    /// Scratch space for intermediate files (`WORKING_DIR`, default `/app/temp`).
+    /// Points at the tmpfs RAM disk on the Radxa deployment.
+    pub working_dir: PathBuf,
+    /// Approximate heap budget shared by the meshes of one conversion
+    /// (`MEMORY_BUDGET_MB`, default 512). Larger meshes are spooled through
+    /// `spool_dir` instead of being held in memory.
+    pub memory_budget: u64,
+    /// Disk-backed directory for mesh spool files (`SPOOL_DIR`, default
+    /// `<config_dir>/spool`). Must not be a tmpfs: spooling a mesh into RAM
+    /// would defeat the memory budget.
+    pub spool_dir: PathBuf,
+    /// Deflate level for generated 3MF packages (`ZIP_COMPRESSION_LEVEL`,
+    /// 0-9, default 6). 0 stores entries uncompressed.
+    pub compression_level: u32,
+    /// Build plate size in millimetres used to arrange objects in generated
+    /// 3MF packages (`BED_WIDTH_MM` / `BED_DEPTH_MM`, default 256 x 256, the
+    /// Bambu Lab X1/P1 bed).
+    pub bed_size: [f32; 2],
+    /// Merge the meshes of one model into a single assembly object made of
+    /// components instead of separate objects (`THREEMF_ASSEMBLY`, default off).
+    pub assembly_output: bool,
+    /// Most bytes one archive may expand to (`ARCHIVE_MAX_SIZE_MB`, default
+    /// 2048). Extraction lands on the tmpfs working dir.
+    pub archive_max_bytes: u64,
+    /// Highest accepted ratio of extracted bytes to archive size
+    /// (`ARCHIVE_MAX_RATIO`, default 100). Catches zip bombs.
+    pub archive_max_ratio: u64,
+    /// Most entries one archive may contain (`ARCHIVE_MAX_ENTRIES`, default 10000).
+    pub archive_max_entries: u64,
+    /// Deepest directory nesting of an archive entry path
+    /// (`ARCHIVE_MAX_PATH_DEPTH`, default 32).
+    pub archive_max_depth: usize,
+    /// Levels of archives inside archives that are unpacked too
+    /// (`ARCHIVE_MAX_NESTING`, default 3; 0 keeps inner archives packed).
+    pub archive_max_nesting: u32,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let config_dir = env_path("CONFIG_DIR", "/config");
+        Self {
+            input_dir: env_path("INPUT_DIR", "/input"),
+            output_dir: env_path("OUTPUT_DIR", "/output"),
+            spool_dir: std::env::var_os("SPOOL_DIR")
+                .map_or_else(|| config_dir.join("spool"), PathBuf::from),
+            config_dir,
+            web_port: env_u64("WEB_PORT", 8080).min(u16::MAX as u64) as u16,
+            settle_delay: Duration::from_secs(env_u64("WATCHER_SETTLE_SECS", 5)),
+            poll_interval: Duration::from_secs(env_u64("WATCHER_POLL_SECS", 2).max(1)),
+            watcher_polling: env_bool("WATCHER_POLLING", false),
+            max_attempts: env_u64("JOB_MAX_ATTEMPTS", 3).clamp(1, u32::MAX as u64) as u32,
+            job_retention: env_u64("JOB_RETENTION", 500) as usize,
+            working_dir: env_path("WORKING_DIR", "/app/temp"),
+            memory_budget: env_u64("MEMORY_BUDGET_MB", 512).max(1) * 1024 * 1024,
+            compression_level: env_u64("ZIP_COMPRESSION_LEVEL", 6).min(9) as u32,
+            bed_size: [
+                env_u64("BED_WIDTH_MM", 256).max(1) as f32,
+                env_u64("BED_DEPTH_MM", 256).max(1) as f32,
+            ],
+            assembly_output: env_bool("THREEMF_ASSEMBLY", false),
+            archive_max_bytes: env_u64("ARCHIVE_MAX_SIZE_MB", 2048).max(1) * 1024 * 1024,
+            archive_max_ratio: env_u64("ARCHIVE_MAX_RATIO", 100).max(1),
+            archive_max_entries: env_u64("ARCHIVE_MAX_ENTRIES", 10_000).max(1),
+            archive_max_depth: env_u64("ARCHIVE_MAX_PATH_DEPTH", 32).max(1) as usize,
+            archive_max_nesting: env_u64("ARCHIVE_MAX_NESTING", 3).min(16) as u32,
+        }
+    }
+}
+
+fn env_path(key: &str, default: &str) -> PathBuf {
+    std::env::var_os(key)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(default))
+}
+
+fn env_u64(key: &str, default: u64) -> u64 {
+    match std::env::var(key) {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            log::warn!("Config: invalid {}={:?}, using {}", key, value, default);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+fn env_bool(key: &str, default: bool) -> bool {
+    match std::env::var(key) {
+        Ok(value) => matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        ),
+        Err(_) => default,
+    }
+}
@@ -0,0 +1,334 @@
+//! Manyfold Datapackage
+//!
+//! Governance: .agent/skills/architectural_guidelines/SKILL.md (Level 3: Processing Pipeline)
+//!
+//! `datapackage.json` is the bridge Manyfold reads from every model folder
+//! (see Data_Architecture_and_Logic.md): `name` is the folder slug, `title`
+//! the display name, and `resources` link the model and image files by
+//! relative path. The schema follows the legacy `DatapackageGenerator`.
+//!
+//! A datapackage already in the output folder (from an earlier run, or
+//! written back by Manyfold) is merged rather than overwritten: its values
+//! win, generated values only fill gaps, and fields this module does not
+//! know are kept.
+
+use crate::preview::tokenize;
+use crate::project::walk_files;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "datapackage.json";
+pub const SCHEMA_URL: &str = "https://manyfold.app/profiles/0.0/datapackage.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Datapackage {
+    #[serde(rename = "$schema", default)]
+    pub schema: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub caption: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub resources: Vec<Resource>,
+    #[serde(default)]
+    pub contributors: Vec<Contributor>,
+    #[serde(default)]
+    pub links: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub licenses: Vec<License>,
+    /// Nested archives the files were unpacked from (Frictionless `sources`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<Source>,
+    /// Fields not generated here, preserved on merge.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// One file of the model folder.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Resource {
+    pub name: String,
+    /// Relative to the model folder, with `/` separators.
+    pub path: String,
+    #[serde(default)]
+    pub mediatype: String,
+    /// Up axis; set for model files only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub up: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presupported: Option<bool>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Contributor {
+    pub title: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// Terms the model is shared under (Frictionless `licenses`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct License {
+    /// Identifier such as `CC-BY-4.0`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// Human-readable name, for licenses given as free text.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title: String,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// Where files of the model came from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    /// Archive chain, outermost first: `ork.zip > supported.zip`.
+    pub title: String,
+    /// Folder of the model holding the unpacked files, `/`-separated.
+    #[serde(default)]
+    pub path: String,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Datapackage {
+    pub fn new(name: &str, title: &str) -> Self {
+        Self {
+            schema: SCHEMA_URL.to_string(),
+            name: name.to_string(),
+            title: title.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Lists the file at `path` (relative to the model folder).
+    pub fn add_resource(&mut self, path: &Path) {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let (stem, ext) = match path.rsplit_once('.') {
+            Some((stem, ext)) if !ext.contains('/') => (stem, ext.to_ascii_lowercase()),
+            _ => (path.as_str(), String::new()),
+        };
+        let (mediatype, model) = mediatype(&ext);
+        self.resources.push(Resource {
+            name: stem.to_string(),
+            path: path.clone(),
+            mediatype: mediatype.to_string(),
+            up: model.then(|| "+z".to_string()),
+            presupported: model.then(|| is_presupported(&path)),
+            other: Map::new(),
+        });
+    }
+
+    pub fn add_contributor(&mut self, title: &str, role: &str) {
+        self.contributors.push(Contributor {
+            title: title.to_string(),
+            roles: vec![role.to_string()],
+            other: Map::new(),
+        });
+    }
+
+    /// Adds a license given as text: a single word (`CC-BY-4.0`) is taken as
+    /// its identifier, anything else as its title.
+    pub fn add_license(&mut self, text: &str) {
+        let text = text.trim();
+        let license = if text.contains(char::is_whitespace) {
+            License {
+                title: text.to_string(),
+                ..License::default()
+            }
+        } else {
+            License {
+                name: text.to_string(),
+                ..License::default()
+            }
+        };
+        if !text.is_empty() && !self.licenses.contains(&license) {
+            self.licenses.push(license);
+        }
+    }
+
+    /// Records that the files in `folder` (relative to the model folder) were
+    /// unpacked from the archive `chain`.
+    pub fn add_source(&mut self, chain: &str, folder: &Path) {
+        let path = folder.to_string_lossy().replace('\\', "/");
+        if !self
+            .sources
+            .iter()
+            .any(|s| s.title == chain && s.path == path)
+        {
+            self.sources.push(Source {
+                title: chain.to_string(),
+                path,
+                other: Map::new(),
+            });
+        }
+    }
+
+    /// Reads an existing `datapackage.json`.
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| anyhow::anyhow!("Datapackage {:?} is invalid: {}", path, e))
+    }
+
+    /// Merges `generated` into this (existing) datapackage: empty fields are
+    /// filled, and keywords, resources (by path), contributors (by title),
+    /// sources and links missing here are appended. Licenses are taken only
+    /// when this package names none: they are not additive.
+    pub fn merge(&mut self, generated: Datapackage) {
+        for (field, value) in [
+            (&mut self.schema, generated.schema),
+            (&mut self.name, generated.name),
+            (&mut self.title, generated.title),
+            (&mut self.caption, generated.caption),
+            (&mut self.description, generated.description),
+        ] {
+            if field.is_empty() {
+                *field = value;
+            }
+        }
+        for keyword in generated.keywords {
+            if !self.keywords.contains(&keyword) {
+                self.keywords.push(keyword);
+            }
+        }
+        for resource in generated.resources {
+            if !self.resources.iter().any(|r| r.path == resource.path) {
+                self.resources.push(resource);
+            }
+        }
+        for contributor in generated.contributors {
+            if !self
+                .contributors
+                .iter()
+                .any(|c| c.title == contributor.title)
+            {
+                self.contributors.push(contributor);
+            }
+        }
+        if self.licenses.is_empty() {
+            self.licenses = generated.licenses;
+        }
+        for source in generated.sources {
+            if !self.sources.contains(&source) {
+                self.sources.push(source);
+            }
+        }
+        for link in generated.links {
+            if !self.links.contains(&link) {
+                self.links.push(link);
+            }
+        }
+        for (key, value) in generated.other {
+            self.other.entry(key).or_insert(value);
+        }
+    }
+
+    /// Writes the datapackage atomically (temp file + rename).
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        {
+            let mut out = std::fs::File::create(&tmp)?;
+            serde_json::to_writer_pretty(&mut out, self)?;
+            out.write_all(b"\n")?;
+        }
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Generates the datapackage of `model_dir`, listing every file in it:
+/// models first, then `previews` (relative paths, best first), then the
+/// remaining files.
+pub fn generate(
+    model_dir: &Path,
+    name: &str,
+    title: &str,
+    previews: &[PathBuf],
+) -> std::io::Result<Datapackage> {
+    let mut files: Vec<(u8, usize, PathBuf)> = walk_files(model_dir)?
+        .into_iter()
+        .filter_map(|file| {
+            let relative = file.strip_prefix(model_dir).ok()?.to_path_buf();
+            let file_name = relative.file_name()?.to_string_lossy();
+            if file_name == FILE_NAME || file_name.ends_with(".part") {
+                return None;
+            }
+            let ext = relative
+                .extension()
+                .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            let preview = previews.iter().position(|p| *p == relative);
+            let group = match (mediatype(&ext), preview) {
+                ((_, true), _) => 0,
+                (_, Some(_)) => 1,
+                ((mediatype, _), None) if mediatype.starts_with("image/") => 2,
+                _ => 3,
+            };
+            Some((group, preview.unwrap_or(0), relative))
+        })
+        .collect();
+    files.sort();
+
+    let mut package = Datapackage::new(name, title);
+    for (_, _, relative) in files {
+        package.add_resource(&relative);
+    }
+    Ok(package)
+}
+
+/// Display title from a file or folder name: `car_v1` -> `Car V1`.
+pub fn title_from_name(name: &str) -> String {
+    name.split(|c: char| c == '-' || c == '_' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Media type of a file extension, and whether the file is a model.
+fn mediatype(ext: &str) -> (&'static str, bool) {
+    match ext {
+        "3mf" => ("model/3mf", true),
+        "stl" => ("model/stl", true),
+        "obj" => ("model/obj", true),
+        "ply" => ("model/x-ply", true),
+        "amf" => ("application/x-amf", true),
+        "png" => ("image/png", false),
+        "jpg" | "jpeg" => ("image/jpeg", false),
+        "webp" => ("image/webp", false),
+        "gif" => ("image/gif", false),
+        "bmp" => ("image/bmp", false),
+        "pdf" => ("application/pdf", false),
+        "txt" => ("text/plain", false),
+        "md" => ("text/markdown", false),
+        "html" | "htm" => ("text/html", false),
+        "gcode" | "gco" => ("text/x-gcode", false),
+        _ => ("application/octet-stream", false),
+    }
+}
+
+/// `supported/knight.stl`, `knight_presupported.stl`; not `unsupported`.
+fn is_presupported(path: &str) -> bool {
+    tokenize(&path.replace('/', " "))
+        .iter()
+        .any(|token| matches!(token.as_str(), "supported" | "presupported" | "presup"))
+}
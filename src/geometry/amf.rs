@@ -0,0 +1,345 @@
+//! AMF (Additive Manufacturing File) Reader
+//!
+//! Governance: .agent/skills/geometry_governance/SKILL.md
+//!
+//! AMF (ISO/ASTM 52915) is XML, stored either plain or as a ZIP archive with
+//! a single `.amf` entry. Every `<object>` holds a `<mesh>` with indexed
+//! `<vertices>` and one or more `<volume>`s of `<triangle>`s; each object
+//! becomes one mesh. A volume's `materialid` refers to a `<material>` whose
+//! `<color>` becomes the triangles' display colour. Coordinates are scaled
+//! from the document `unit` to millimetres. Constellations, textures and
+//! curved-triangle edges are ignored.
+
+use super::mesh::{Material, ParsedObject, ParsedObjects};
+use super::stl::is_degenerate;
+use super::ProgressSink;
+use crate::progress::Stage;
+use crate::threemf::attr;
+use quick_xml::events::Event;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Triangles between progress/cancellation checks.
+const PROGRESS_INTERVAL: u64 = 16 * 1024;
+/// Colour of volumes whose material has no `<color>`.
+const DEFAULT_COLOR: [u8; 4] = [0x80, 0x80, 0x80, 0xFF];
+
+/// Why an AMF file was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmfError {
+    /// Zipped AMF that is not a readable archive or has no `.amf` entry.
+    InvalidZip(String),
+    /// Not well-formed XML, or a value that is not a number.
+    InvalidXml(String),
+    /// A triangle references a vertex outside its object.
+    BadIndex { object: String, index: i64 },
+    /// A vertex coordinate is NaN or infinite.
+    NonFinite { object: String },
+    /// No usable triangles remain after skipping degenerate ones.
+    Empty { degenerate: u64 },
+}
+
+impl AmfError {
+    /// Stable identifier recorded as the job's failure reason.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AmfError::InvalidZip(_) => "amf_invalid_zip",
+            AmfError::InvalidXml(_) => "amf_invalid_xml",
+            AmfError::BadIndex { .. } => "amf_bad_index",
+            AmfError::NonFinite { .. } => "amf_non_finite",
+            AmfError::Empty { .. } => "amf_empty",
+        }
+    }
+}
+
+impl fmt::Display for AmfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmfError::InvalidZip(message) => write!(f, "not a valid zipped AMF: {}", message),
+            AmfError::InvalidXml(message) => write!(f, "AMF is not valid: {}", message),
+            AmfError::BadIndex { object, index } => {
+                write!(f, "object {:?} references missing vertex {}", object, index)
+            }
+            AmfError::NonFinite { object } => {
+                write!(f, "object {:?} has a NaN or infinite coordinate", object)
+            }
+            AmfError::Empty { degenerate: 0 } => write!(f, "AMF contains no triangles"),
+            AmfError::Empty { degenerate } => write!(
+                f,
+                "AMF contains no usable triangles ({} degenerate triangles skipped)",
+                degenerate
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AmfError {}
+
+/// Parses a plain or zipped AMF file.
+pub fn parse_amf(path: &Path, progress: &dyn ProgressSink) -> anyhow::Result<ParsedObjects> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len().max(1);
+    let mut magic = [0u8; 4];
+    let zipped = file.read_exact(&mut magic).is_ok() && &magic == b"PK\x03\x04";
+    file.seek(SeekFrom::Start(0))?;
+
+    if !zipped {
+        return parse_document(BufReader::new(file), len, progress);
+    }
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| AmfError::InvalidZip(e.to_string()))?;
+    let name = archive
+        .file_names()
+        .find(|n| n.to_ascii_lowercase().ends_with(".amf"))
+        .map(str::to_string)
+        .ok_or_else(|| AmfError::InvalidZip("archive has no .amf entry".to_string()))?;
+    let entry = archive
+        .by_name(&name)
+        .map_err(|e| AmfError::InvalidZip(e.to_string()))?;
+    let size = entry.size().max(1);
+    parse_document(BufReader::new(entry), size, progress)
+}
+
+/// Element whose text content is being collected.
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Coordinate(usize),
+    Index(usize),
+    Color(usize),
+    Name,
+}
+
+/// A volume's triangles before the material is resolved.
+struct Volume {
+    material: Option<String>,
+    triangles: Vec<[i64; 3]>,
+}
+
+fn parse_document<R: BufRead>(
+    input: R,
+    total: u64,
+    progress: &dyn ProgressSink,
+) -> anyhow::Result<ParsedObjects> {
+    let xml_error = |message: String| anyhow::Error::from(AmfError::InvalidXml(message));
+    let mut reader = quick_xml::Reader::from_reader(input);
+    let mut buf = Vec::new();
+
+    let mut scale = 1.0f32;
+    let mut objects: Vec<ParsedObject> = Vec::new();
+    // Material id -> (name, colour); resolved after the whole document was
+    // read because materials may follow the objects using them.
+    let mut material_defs: HashMap<String, (Option<String>, [u8; 4])> = HashMap::new();
+    let mut pending: Vec<(usize, Vec<[f32; 3]>, Vec<Volume>)> = Vec::new();
+
+    let mut object: Option<(String, Option<String>)> = None;
+    let mut vertices: Vec<[f32; 3]> = Vec::new();
+    let mut volumes: Vec<Volume> = Vec::new();
+    let mut vertex = [0f32; 3];
+    let mut triangle = [0i64; 3];
+    let mut material: Option<(String, Option<String>, [u8; 4])> = None;
+    let mut field: Option<Field> = None;
+    let mut metadata_name = false;
+    let mut text = String::new();
+    let mut triangles_read = 0u64;
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| xml_error(e.to_string()))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let empty = matches!(event, Event::Empty(_));
+                text.clear();
+                field = None;
+                match e.local_name().as_ref() {
+                    b"amf" => {
+                        scale = match attr(e, b"unit").as_deref() {
+                            Some("inch") => 25.4,
+                            Some("feet") => 304.8,
+                            Some("meter") => 1000.0,
+                            Some("micron") => 0.001,
+                            _ => 1.0,
+                        };
+                    }
+                    b"object" if !empty => {
+                        let id = attr(e, b"id").unwrap_or_default();
+                        object = Some((id, None));
+                        vertices.clear();
+                        volumes.clear();
+                    }
+                    b"material" if !empty => {
+                        let id = attr(e, b"id").unwrap_or_default();
+                        material = Some((id, None, DEFAULT_COLOR));
+                    }
+                    b"volume" if !empty => volumes.push(Volume {
+                        material: attr(e, b"materialid"),
+                        triangles: Vec::new(),
+                    }),
+                    b"metadata" => {
+                        metadata_name = attr(e, b"type").as_deref() == Some("name");
+                        if metadata_name {
+                            field = Some(Field::Name);
+                        }
+                    }
+                    b"x" => field = Some(Field::Coordinate(0)),
+                    b"y" => field = Some(Field::Coordinate(1)),
+                    b"z" => field = Some(Field::Coordinate(2)),
+                    b"v1" => field = Some(Field::Index(0)),
+                    b"v2" => field = Some(Field::Index(1)),
+                    b"v3" => field = Some(Field::Index(2)),
+                    b"r" => field = Some(Field::Color(0)),
+                    b"g" => field = Some(Field::Color(1)),
+                    b"b" => field = Some(Field::Color(2)),
+                    b"a" => field = Some(Field::Color(3)),
+                    _ => {}
+                }
+            }
+            Event::Text(ref t) if field.is_some() => {
+                text.push_str(&t.decode().map_err(|e| xml_error(e.to_string()))?);
+            }
+            Event::GeneralRef(ref r) if field == Some(Field::Name) => {
+                if let Some(c) = r.resolve_char_ref().map_err(|e| xml_error(e.to_string()))? {
+                    text.push(c);
+                } else {
+                    let name = r.decode().map_err(|e| xml_error(e.to_string()))?;
+                    let resolved = quick_xml::escape::resolve_predefined_entity(&name);
+                    text.push_str(resolved.unwrap_or_default());
+                }
+            }
+            Event::End(ref e) => {
+                let local = e.local_name();
+                let number = |text: &str| -> anyhow::Result<f64> {
+                    text.trim()
+                        .parse::<f64>()
+                        .map_err(|_| xml_error(format!("invalid number {:?}", text.trim())))
+                };
+                match (field.take(), local.as_ref()) {
+                    (Some(Field::Coordinate(axis)), _) => vertex[axis] = number(&text)? as f32,
+                    (Some(Field::Index(slot)), _) => triangle[slot] = number(&text)? as i64,
+                    // Colours may be formulas; those keep the default.
+                    (Some(Field::Color(channel)), _) => {
+                        if let (Some((_, _, color)), Ok(value)) = (material.as_mut(), number(&text))
+                        {
+                            color[channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                        }
+                    }
+                    (Some(Field::Name), _) if metadata_name => {
+                        let name = text.trim().to_string();
+                        if let Some((_, slot, _)) = material.as_mut() {
+                            slot.get_or_insert(name);
+                        } else if let Some((_, slot)) = object.as_mut() {
+                            slot.get_or_insert(name);
+                        }
+                    }
+                    (_, b"vertex") => {
+                        let v = vertex.map(|c| c * scale);
+                        if v.iter().any(|c| !c.is_finite()) {
+                            let id = object
+                                .as_ref()
+                                .map(|(id, _)| id.clone())
+                                .unwrap_or_default();
+                            return Err(AmfError::NonFinite { object: id }.into());
+                        }
+                        vertices.push(v);
+                        vertex = [0.0; 3];
+                    }
+                    (_, b"triangle") => {
+                        if let Some(volume) = volumes.last_mut() {
+                            volume.triangles.push(triangle);
+                        }
+                        triangle = [0; 3];
+                        triangles_read += 1;
+                        if triangles_read % PROGRESS_INTERVAL == 0 {
+                            let position = reader.buffer_position();
+                            progress.progress(Stage::Parsing, position as f64 / total as f64);
+                            progress.check_cancelled()?;
+                        }
+                    }
+                    (_, b"material") => {
+                        if let Some((id, name, color)) = material.take() {
+                            material_defs.insert(id, (name, color));
+                        }
+                    }
+                    (_, b"object") => {
+                        if let Some((id, name)) = object.take() {
+                            let name = name.unwrap_or_else(|| format!("object {}", id));
+                            objects.push(ParsedObject {
+                                name,
+                                corners: Vec::new(),
+                                materials: Vec::new(),
+                                degenerate: 0,
+                            });
+                            pending.push((
+                                objects.len() - 1,
+                                std::mem::take(&mut vertices),
+                                std::mem::take(&mut volumes),
+                            ));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    progress.progress(Stage::Parsing, 1.0);
+
+    let mut materials: Vec<Material> = Vec::new();
+    let mut material_ids: HashMap<String, u32> = HashMap::new();
+    let mut uses_materials = false;
+    for (index, vertices, volumes) in pending {
+        let object = &mut objects[index];
+        for volume in volumes {
+            let material = volume.material.map(|id| {
+                uses_materials = true;
+                *material_ids.entry(id.clone()).or_insert_with(|| {
+                    let (name, color) = material_defs
+                        .get(&id)
+                        .cloned()
+                        .unwrap_or((None, DEFAULT_COLOR));
+                    materials.push(Material {
+                        name: name.unwrap_or_else(|| format!("material {}", id)),
+                        color,
+                    });
+                    materials.len() as u32 - 1
+                })
+            });
+            for indices in volume.triangles {
+                let mut tri = [[0f32; 3]; 3];
+                for (corner, index) in tri.iter_mut().zip(indices) {
+                    *corner = usize::try_from(index)
+                        .ok()
+                        .and_then(|i| vertices.get(i))
+                        .copied()
+                        .ok_or_else(|| AmfError::BadIndex {
+                            object: object.name.clone(),
+                            index,
+                        })?;
+                }
+                if is_degenerate(&tri) {
+                    object.degenerate += 1;
+                    continue;
+                }
+                object.corners.extend_from_slice(&tri);
+                object.materials.push(material);
+            }
+        }
+    }
+
+    let degenerate: u64 = objects.iter().map(|o| o.degenerate).sum();
+    objects.retain(|o| !o.corners.is_empty());
+    if objects.is_empty() {
+        return Err(AmfError::Empty { degenerate }.into());
+    }
+    if !uses_materials {
+        for object in &mut objects {
+            object.materials.clear();
+        }
+    }
+    Ok(ParsedObjects { objects, materials })
+}
@@ -0,0 +1,268 @@
+//! Indexed Mesh Representation
+//!
+//! Every input format is reduced to this structure before it is written to 3MF.
+
+use super::ProgressSink;
+use crate::progress::Stage;
+use std::collections::BTreeMap;
+use std::io;
+
+/// Read access to an indexed mesh, whether it lives in memory ([`Mesh`]) or
+/// in spool files ([`super::SpooledMesh`]). The 3MF writer only needs to walk
+/// vertices and triangles once, in order.
+pub trait MeshSource {
+    fn name(&self) -> &str;
+    fn vertex_count(&self) -> usize;
+    fn triangle_count(&self) -> usize;
+    fn for_each_vertex(&self, f: &mut dyn FnMut([f32; 3]) -> io::Result<()>) -> io::Result<()>;
+    fn for_each_triangle(&self, f: &mut dyn FnMut([u32; 3]) -> io::Result<()>) -> io::Result<()>;
+
+    /// Material per triangle as an index into the package's material list
+    /// (`None` uses the object default). Empty if the mesh has no materials.
+    fn triangle_materials(&self) -> &[Option<u32>] {
+        &[]
+    }
+
+    /// Heap held by the mesh until it is packaged; charged against the
+    /// memory budget of the meshes loaded after it. Spooled meshes hold none.
+    fn heap_bytes(&self) -> u64 {
+        0
+    }
+
+    /// Axis-aligned bounding box; `None` for a mesh without vertices. The
+    /// default walks all vertices once.
+    fn bounds(&self) -> io::Result<Option<Bounds>> {
+        let mut bounds: Option<Bounds> = None;
+        self.for_each_vertex(&mut |v| {
+            match bounds.as_mut() {
+                Some(b) => b.include(v),
+                None => bounds = Some(Bounds { min: v, max: v }),
+            }
+            Ok(())
+        })?;
+        Ok(bounds)
+    }
+}
+
+/// Named display colour, written as a 3MF `<base>` material.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    /// sRGB with alpha.
+    pub color: [u8; 4],
+}
+
+/// One named object of a multi-object format (OBJ groups, AMF objects) as a
+/// flat corner list.
+pub struct ParsedObject {
+    pub name: String,
+    /// Three corners per triangle.
+    pub corners: Vec<[f32; 3]>,
+    /// Material per triangle (index into [`ParsedObjects::materials`]); empty
+    /// if the object uses no materials.
+    pub materials: Vec<Option<u32>>,
+    /// Zero-area triangles that were skipped.
+    pub degenerate: u64,
+}
+
+/// Objects and materials read from one multi-object file.
+pub struct ParsedObjects {
+    /// Objects in file order; objects without faces are dropped.
+    pub objects: Vec<ParsedObject>,
+    /// Materials in order of first use.
+    pub materials: Vec<Material>,
+}
+
+/// Axis-aligned bounding box in model units (millimetres).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Bounds {
+    pub fn include(&mut self, v: [f32; 3]) {
+        for (axis, value) in v.into_iter().enumerate() {
+            self.min[axis] = self.min[axis].min(value);
+            self.max[axis] = self.max[axis].max(value);
+        }
+    }
+
+    pub fn size(&self) -> [f32; 3] {
+        [
+            self.max[0] - self.min[0],
+            self.max[1] - self.min[1],
+            self.max[2] - self.min[2],
+        ]
+    }
+}
+
+/// Indexed triangle mesh with shared (deduplicated) vertices.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    /// Object name written to the 3MF (usually the source file name).
+    pub name: String,
+    pub vertices: Vec<[f32; 3]>,
+    pub triangles: Vec<[u32; 3]>,
+    /// Per-triangle materials (see [`MeshSource::triangle_materials`]).
+    pub materials: Vec<Option<u32>>,
+}
+
+impl Mesh {
+    /// Builds an indexed mesh from a flat corner list (three corners per
+    /// triangle), merging bit-identical positions. `-0.0` and `0.0` are treated
+    /// as the same coordinate.
+    pub fn from_corners(
+        name: String,
+        corners: &[[f32; 3]],
+        progress: &dyn ProgressSink,
+    ) -> anyhow::Result<Self> {
+        let mut unique: BTreeMap<[u32; 3], u32> = BTreeMap::new();
+        let mut vertices = Vec::new();
+        let total = corners.len() / 3;
+        let mut triangles = Vec::with_capacity(total);
+        let step = (total / 100).max(1);
+
+        for (t, tri) in corners.chunks_exact(3).enumerate() {
+            let mut indices = [0u32; 3];
+            for (slot, corner) in indices.iter_mut().zip(tri) {
+                let key = vertex_key(corner);
+                *slot = *unique.entry(key).or_insert_with(|| {
+                    vertices.push(*corner);
+                    (vertices.len() - 1) as u32
+                });
+            }
+            triangles.push(indices);
+
+            if t % step == 0 {
+                progress.progress(Stage::Deduplicating, t as f64 / total as f64);
+                progress.check_cancelled()?;
+            }
+        }
+        progress.progress(Stage::Deduplicating, 1.0);
+
+        Ok(Self {
+            name,
+            vertices,
+            triangles,
+            materials: Vec::new(),
+        })
+    }
+}
+
+impl MeshSource for Mesh {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    fn for_each_vertex(&self, f: &mut dyn FnMut([f32; 3]) -> io::Result<()>) -> io::Result<()> {
+        self.vertices.iter().try_for_each(|v| f(*v))
+    }
+
+    fn for_each_triangle(&self, f: &mut dyn FnMut([u32; 3]) -> io::Result<()>) -> io::Result<()> {
+        self.triangles.iter().try_for_each(|t| f(*t))
+    }
+
+    fn triangle_materials(&self) -> &[Option<u32>] {
+        &self.materials
+    }
+
+    fn heap_bytes(&self) -> u64 {
+        (self.vertices.capacity() * std::mem::size_of::<[f32; 3]>()
+            + self.triangles.capacity() * std::mem::size_of::<[u32; 3]>()
+            + self.materials.capacity() * std::mem::size_of::<Option<u32>>()) as u64
+    }
+}
+
+/// Splits a planar polygon into triangles by ear clipping in the plane of its
+/// Newell normal. Returns index triples into `polygon`, keeping its winding.
+/// Self-intersecting or degenerate outlines fall back to a fan for the
+/// remainder.
+pub(super) fn triangulate(polygon: &[[f32; 3]]) -> Vec<[usize; 3]> {
+    let n = polygon.len();
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Newell normal; project onto the plane of its two minor axes.
+    let mut normal = [0f64; 3];
+    for i in 0..n {
+        let a = polygon[i].map(f64::from);
+        let b = polygon[(i + 1) % n].map(f64::from);
+        normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+        normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+        normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+    }
+    let major = (0..3)
+        .max_by(|&a, &b| normal[a].abs().total_cmp(&normal[b].abs()))
+        .unwrap_or(2);
+    let (u, v) = match major {
+        0 => (1, 2),
+        1 => (2, 0),
+        _ => (0, 1),
+    };
+    let points: Vec<[f64; 2]> = polygon
+        .iter()
+        .map(|p| [f64::from(p[u]), f64::from(p[v])])
+        .collect();
+    // Counter-clockwise in the projection when the normal points along +major.
+    let orientation = if normal[major] >= 0.0 { 1.0 } else { -1.0 };
+    let cross = |a: usize, b: usize, c: usize| -> f64 {
+        let (pa, pb, pc) = (points[a], points[b], points[c]);
+        ((pb[0] - pa[0]) * (pc[1] - pa[1]) - (pb[1] - pa[1]) * (pc[0] - pa[0])) * orientation
+    };
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    let mut i = 0;
+    let mut since_last_ear = 0;
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let (a, b, c) = (
+            remaining[(i + len - 1) % len],
+            remaining[i % len],
+            remaining[(i + 1) % len],
+        );
+        let convex = cross(a, b, c) > 0.0;
+        let is_ear = convex
+            && remaining.iter().all(|&p| {
+                p == a
+                    || p == b
+                    || p == c
+                    || !(cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0)
+            });
+        if is_ear {
+            triangles.push([a, b, c]);
+            remaining.remove(i % len);
+            since_last_ear = 0;
+        } else {
+            i += 1;
+            since_last_ear += 1;
+            if since_last_ear > len {
+                break;
+            }
+        }
+        i %= remaining.len();
+    }
+    for k in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[k], remaining[k + 1]]);
+    }
+    triangles
+}
+
+pub(super) fn vertex_key(v: &[f32; 3]) -> [u32; 3] {
+    // Adding +0.0 folds -0.0 into 0.0 so mirrored exports still share vertices.
+    [
+        (v[0] + 0.0).to_bits(),
+        (v[1] + 0.0).to_bits(),
+        (v[2] + 0.0).to_bits(),
+    ]
+}
@@ -0,0 +1,262 @@
+//! Geometry Engine
+//!
+//! Governance: .agent/skills/geometry_governance/SKILL.md
+//!
+//! In-process replacement for the legacy `stl23mf` plugin binary: parses mesh
+//! files (STL, OBJ, PLY, AMF), deduplicates vertices and hands indexed meshes
+//! to the 3MF packager.
+//! Progress and cancellation flow through [`ProgressSink`] instead of
+//! `PROGRESS:` lines on stdout.
+
+mod amf;
+mod mesh;
+mod obj;
+mod ply;
+mod spool;
+mod stl;
+
+pub use amf::{parse_amf, AmfError};
+pub use mesh::{Bounds, Material, Mesh, MeshSource, ParsedObjects};
+pub use obj::{parse_obj, ObjError};
+pub use ply::{parse_ply, PlyError};
+pub use spool::SpooledMesh;
+pub use stl::{parse_stl, StlError, StlReader};
+
+use crate::progress::Stage;
+use crate::threemf;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// Receives progress updates from long-running geometry operations.
+pub trait ProgressSink {
+    /// `fraction` is in `0.0..=1.0` for the current stage.
+    fn progress(&self, stage: Stage, fraction: f64);
+
+    /// Called at the same points as `progress`; an error aborts the conversion.
+    fn check_cancelled(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Tuning knobs for [`convert_to_3mf`].
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    /// Write `Metadata/model_settings.config` so Bambu Studio / OrcaSlicer keep
+    /// object names and plate assignment.
+    pub bambu_settings: bool,
+    /// Approximate heap budget in bytes shared by all meshes of the package.
+    /// STLs expected to need more than what the meshes loaded before them
+    /// left over are streamed through spool files in `spool_dir`.
+    pub memory_budget: u64,
+    /// Directory for spool files (`SPOOL_DIR` in production, on disk).
+    pub spool_dir: PathBuf,
+    /// Deflate level for package entries, 0 (store) to 9.
+    pub compression_level: u32,
+    /// PNG or JPEG embedded as the package thumbnail (`Metadata/thumbnail.*`).
+    pub thumbnail: Option<PathBuf>,
+    /// Build plate size (width, depth) in millimetres used to arrange objects.
+    pub bed_size: [f32; 2],
+    /// Emit one object with this name composed of every mesh as a component
+    /// (a multi-part kit opens as a single assembly) instead of one object per
+    /// mesh.
+    pub assembly: Option<String>,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            bambu_settings: true,
+            memory_budget: 512 * 1024 * 1024,
+            spool_dir: std::env::temp_dir(),
+            compression_level: 6,
+            thumbnail: None,
+            bed_size: [256.0, 256.0],
+            assembly: None,
+        }
+    }
+}
+
+/// Rough heap cost per triangle of the in-memory path: the corner list, the
+/// index triple and the vertex/dedup-map share.
+const IN_MEMORY_BYTES_PER_TRIANGLE: u64 = 80;
+/// Typical size of one `facet ... endfacet` block in an ASCII STL.
+const ASCII_BYTES_PER_TRIANGLE: u64 = 250;
+
+/// Per-object statistics of a conversion.
+#[derive(Debug, Clone)]
+pub struct ObjectSummary {
+    pub name: String,
+    pub source: PathBuf,
+    pub vertices: usize,
+    pub triangles: usize,
+    /// Zero-area triangles dropped from the input.
+    pub degenerate: u64,
+}
+
+/// Result of a successful conversion.
+#[derive(Debug, Clone)]
+pub struct ConversionSummary {
+    pub output: PathBuf,
+    pub objects: Vec<ObjectSummary>,
+    pub bytes_written: u64,
+    /// Why `options.thumbnail` was not embedded; `None` if it was, or if no
+    /// thumbnail was given.
+    pub thumbnail_skipped: Option<String>,
+}
+
+/// Converts one or more mesh files (STL, OBJ, PLY, AMF) into a single 3MF.
+/// STL and PLY files become one object each; an OBJ contributes one object
+/// per `o`/`g` group and an AMF one per `<object>`.
+///
+/// Every mesh stays loaded until the package is written, so the meshes share
+/// `options.memory_budget`: each in-memory mesh is charged against it, and an
+/// STL that does not fit into what is left is spooled. OBJ, PLY and AMF have
+/// no streaming reader and are always parsed in memory, whatever the budget;
+/// they only count against it, which pushes the STLs after them to spool.
+pub fn convert_to_3mf(
+    inputs: &[PathBuf],
+    output: &Path,
+    options: &ConvertOptions,
+    progress: &dyn ProgressSink,
+) -> anyhow::Result<ConversionSummary> {
+    if inputs.is_empty() {
+        anyhow::bail!("No input meshes given");
+    }
+
+    let mut meshes: Vec<Box<dyn MeshSource>> = Vec::with_capacity(inputs.len());
+    let mut materials: Vec<Material> = Vec::new();
+    let mut objects = Vec::with_capacity(inputs.len());
+    let mut budget = options.memory_budget;
+    for input in inputs {
+        let name = input
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let ext = input
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let loaded = match ext.as_str() {
+            "obj" => load_objects(input, &name, &mut materials, progress, |path| {
+                parse_obj(path, &name, progress)
+            }),
+            "ply" => load_ply(input, name, progress).map(|loaded| vec![loaded]),
+            "amf" => load_objects(input, &name, &mut materials, progress, |path| {
+                parse_amf(path, progress)
+            }),
+            _ => load_stl(input, name, budget, options, progress).map(|loaded| vec![loaded]),
+        }
+        .with_context(|| format!("Failed to parse {:?}", input))?;
+
+        for (mesh, degenerate) in loaded {
+            objects.push(ObjectSummary {
+                name: mesh.name().to_string(),
+                source: input.clone(),
+                vertices: mesh.vertex_count(),
+                triangles: mesh.triangle_count(),
+                degenerate,
+            });
+            budget = budget.saturating_sub(mesh.heap_bytes());
+            meshes.push(mesh);
+        }
+    }
+
+    let refs: Vec<&dyn MeshSource> = meshes.iter().map(|m| m.as_ref()).collect();
+    let package = threemf::write_package(output, &refs, &materials, options, progress)?;
+    log::info!(
+        "Geometry: wrote {:?} ({} object(s), {} bytes)",
+        output,
+        objects.len(),
+        package.bytes
+    );
+
+    Ok(ConversionSummary {
+        output: output.to_path_buf(),
+        objects,
+        bytes_written: package.bytes,
+        thumbnail_skipped: package.thumbnail_skipped,
+    })
+}
+
+/// Loads every object of a multi-object file (OBJ groups, AMF objects) as an
+/// in-memory mesh, using `parse` to read it. The file's materials are
+/// appended to `materials`, shared by all inputs of the package. A file with
+/// a single object keeps the file name as object name.
+fn load_objects(
+    input: &Path,
+    name: &str,
+    materials: &mut Vec<Material>,
+    progress: &dyn ProgressSink,
+    parse: impl FnOnce(&Path) -> anyhow::Result<ParsedObjects>,
+) -> anyhow::Result<Vec<(Box<dyn MeshSource>, u64)>> {
+    log::info!("Geometry: parsing {:?}", input);
+    let parsed = parse(input)?;
+    let offset = materials.len() as u32;
+    materials.extend(parsed.materials);
+    let single = parsed.objects.len() == 1;
+
+    let mut meshes: Vec<(Box<dyn MeshSource>, u64)> = Vec::with_capacity(parsed.objects.len());
+    for object in parsed.objects {
+        let object_name = if single {
+            name.to_string()
+        } else {
+            object.name
+        };
+        let mut mesh = Mesh::from_corners(object_name, &object.corners, progress)?;
+        mesh.materials = object
+            .materials
+            .iter()
+            .map(|m| m.map(|m| m + offset))
+            .collect();
+        meshes.push((Box::new(mesh), object.degenerate));
+    }
+    Ok(meshes)
+}
+
+/// Loads a PLY as one in-memory mesh.
+fn load_ply(
+    input: &Path,
+    name: String,
+    progress: &dyn ProgressSink,
+) -> anyhow::Result<(Box<dyn MeshSource>, u64)> {
+    log::info!("Geometry: parsing {:?}", input);
+    let parsed = parse_ply(input, progress)?;
+    let mesh = Mesh::from_corners(name, &parsed.corners, progress)?;
+    Ok((Box::new(mesh), parsed.degenerate))
+}
+
+/// Loads an STL in memory, or spools it when the estimated in-memory cost
+/// exceeds `budget`, the part of `options.memory_budget` not yet held by
+/// other meshes. Also returns the degenerate triangle count.
+fn load_stl(
+    input: &Path,
+    name: String,
+    budget: u64,
+    options: &ConvertOptions,
+    progress: &dyn ProgressSink,
+) -> anyhow::Result<(Box<dyn MeshSource>, u64)> {
+    let reader = StlReader::open(input)?;
+    let triangles = match reader.triangle_hint() {
+        0 => std::fs::metadata(input)?.len() / ASCII_BYTES_PER_TRIANGLE,
+        n => n as u64,
+    };
+    drop(reader);
+
+    let estimate = triangles.saturating_mul(IN_MEMORY_BYTES_PER_TRIANGLE);
+    if estimate > budget {
+        log::info!(
+            "Geometry: streaming {:?} (~{} MB estimated, {} MB of the budget left)",
+            input,
+            estimate / (1024 * 1024),
+            budget / (1024 * 1024)
+        );
+        let mesh = SpooledMesh::from_stl(input, name, &options.spool_dir, budget, progress)?;
+        let degenerate = mesh.degenerate();
+        return Ok((Box::new(mesh), degenerate));
+    }
+
+    log::info!("Geometry: parsing {:?}", input);
+    let parsed = parse_stl(input, progress)?;
+    let mesh = Mesh::from_corners(name, &parsed.corners, progress)?;
+    Ok((Box::new(mesh), parsed.degenerate))
+}
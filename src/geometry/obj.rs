@@ -0,0 +1,337 @@
+//! Wavefront OBJ Reader
+//!
+//! Governance: .agent/skills/geometry_governance/SKILL.md
+//!
+//! Reads the geometry subset of OBJ: `v` positions and `f` polygons (any
+//! vertex count, `v`, `v/vt`, `v//vn` and `v/vt/vn` references, negative
+//! indices relative to the end). Polygons are ear-clipped into triangles, so
+//! concave faces stay inside their outline. Each `o`/`g` name becomes its own
+//! object; faces that return to an earlier name are appended to it.
+//!
+//! `mtllib` files next to the OBJ are read for the diffuse colour (`Kd`) and
+//! opacity (`d` / `Tr`) of every material. Faces keep the material selected by
+//! `usemtl`, which the 3MF writer emits as `<basematerials>`. Only plain
+//! relative names inside the OBJ's folder are followed, and only to regular
+//! files, so a dropped file cannot read `/etc/passwd`, `/dev/zero` or a FIFO. Normals, texture
+//! coordinates, lines and smoothing groups are ignored.
+
+use super::mesh::{triangulate, Material, ParsedObject, ParsedObjects};
+use super::ProgressSink;
+use crate::progress::Stage;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Component, Path, PathBuf};
+
+/// Lines between progress/cancellation checks.
+const PROGRESS_LINES: usize = 16 * 1024;
+/// Colour of materials that are used but not defined in any `mtllib`.
+const DEFAULT_COLOR: [u8; 4] = [0x80, 0x80, 0x80, 0xFF];
+
+/// Why an OBJ file was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjError {
+    /// A malformed statement.
+    Syntax { line: usize, message: String },
+    /// A face references a vertex that does not exist (yet).
+    BadIndex { line: usize, index: i64 },
+    /// A vertex coordinate is NaN or infinite.
+    NonFinite { line: usize },
+    /// No usable triangles remain after skipping degenerate ones.
+    Empty { degenerate: u64 },
+    /// The file could not be read (after `line` complete lines).
+    Io { line: usize, message: String },
+}
+
+impl ObjError {
+    /// Stable identifier recorded as the job's failure reason.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ObjError::Syntax { .. } => "obj_syntax",
+            ObjError::BadIndex { .. } => "obj_bad_index",
+            ObjError::NonFinite { .. } => "obj_non_finite",
+            ObjError::Empty { .. } => "obj_empty",
+            ObjError::Io { .. } => "obj_io",
+        }
+    }
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Syntax { line, message } => write!(f, "OBJ line {}: {}", line, message),
+            ObjError::BadIndex { line, index } => {
+                write!(
+                    f,
+                    "OBJ line {}: vertex index {} is out of range",
+                    line, index
+                )
+            }
+            ObjError::NonFinite { line } => {
+                write!(f, "OBJ line {}: NaN or infinite coordinate", line)
+            }
+            ObjError::Empty { degenerate: 0 } => write!(f, "OBJ contains no faces"),
+            ObjError::Empty { degenerate } => write!(
+                f,
+                "OBJ contains no usable faces ({} degenerate triangles skipped)",
+                degenerate
+            ),
+            ObjError::Io { line, message } => {
+                write!(f, "OBJ read error after line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// Parses an OBJ file. `default_name` names faces that precede any `o`/`g`.
+pub fn parse_obj(
+    path: &Path,
+    default_name: &str,
+    progress: &dyn ProgressSink,
+) -> anyhow::Result<ParsedObjects> {
+    let io_error = |e: std::io::Error| ObjError::Io {
+        line: 0,
+        message: e.to_string(),
+    };
+    let file = File::open(path).map_err(io_error)?;
+    let len = file.metadata().map_err(io_error)?.len().max(1);
+    let mut reader = BufReader::new(file);
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut objects: Vec<ParsedObject> = Vec::new();
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    let mut library: HashMap<String, [u8; 4]> = HashMap::new();
+    let mut materials: Vec<Material> = Vec::new();
+    let mut material_ids: HashMap<String, u32> = HashMap::new();
+
+    let mut name = default_name.to_string();
+    let mut current: Option<usize> = None;
+    let mut material: Option<u32> = None;
+    let mut polygon: Vec<[f32; 3]> = Vec::new();
+    let mut raw = Vec::new();
+    let mut line_no = 0usize;
+    let mut consumed = 0u64;
+
+    loop {
+        raw.clear();
+        let read = reader
+            .read_until(b'\n', &mut raw)
+            .map_err(|e| ObjError::Io {
+                line: line_no,
+                message: e.to_string(),
+            })?;
+        if read == 0 {
+            break;
+        }
+        // Comments and names are free text in any encoding.
+        let line = String::from_utf8_lossy(&raw);
+        line_no += 1;
+        consumed += read as u64;
+        if line_no % PROGRESS_LINES == 0 {
+            progress.progress(Stage::Parsing, consumed as f64 / len as f64);
+            progress.check_cancelled()?;
+        }
+
+        let content = line.split('#').next().unwrap_or_default();
+        let mut tokens = content.split_ascii_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        match keyword {
+            "v" => {
+                let mut v = [0f32; 3];
+                for slot in &mut v {
+                    let token = tokens.next().ok_or_else(|| ObjError::Syntax {
+                        line: line_no,
+                        message: "vertex needs 3 coordinates".to_string(),
+                    })?;
+                    *slot = token.parse().map_err(|_| ObjError::Syntax {
+                        line: line_no,
+                        message: format!("invalid number {:?}", token),
+                    })?;
+                }
+                if v.iter().any(|c| !c.is_finite()) {
+                    return Err(ObjError::NonFinite { line: line_no }.into());
+                }
+                positions.push(v);
+            }
+            "f" => {
+                polygon.clear();
+                for token in tokens {
+                    let index = token.split('/').next().unwrap_or_default();
+                    let index: i64 = index.parse().map_err(|_| ObjError::Syntax {
+                        line: line_no,
+                        message: format!("invalid vertex reference {:?}", token),
+                    })?;
+                    polygon.push(resolve(&positions, index, line_no)?);
+                }
+                if polygon.len() < 3 {
+                    return Err(ObjError::Syntax {
+                        line: line_no,
+                        message: format!("face has only {} vertices", polygon.len()),
+                    }
+                    .into());
+                }
+
+                let object = *current.get_or_insert_with(|| {
+                    *by_name.entry(name.clone()).or_insert_with(|| {
+                        objects.push(ParsedObject {
+                            name: name.clone(),
+                            corners: Vec::new(),
+                            materials: Vec::new(),
+                            degenerate: 0,
+                        });
+                        objects.len() - 1
+                    })
+                });
+                let object = &mut objects[object];
+                for [a, b, c] in triangulate(&polygon) {
+                    let tri = [polygon[a], polygon[b], polygon[c]];
+                    if super::stl::is_degenerate(&tri) {
+                        object.degenerate += 1;
+                        continue;
+                    }
+                    object.corners.extend_from_slice(&tri);
+                    object.materials.push(material);
+                }
+            }
+            "o" | "g" => {
+                let rest: Vec<&str> = tokens.collect();
+                name = if rest.is_empty() {
+                    default_name.to_string()
+                } else {
+                    rest.join(" ")
+                };
+                current = None;
+            }
+            "usemtl" => {
+                let rest: Vec<&str> = tokens.collect();
+                let mtl = rest.join(" ");
+                material = if mtl.is_empty() {
+                    None
+                } else {
+                    Some(*material_ids.entry(mtl.clone()).or_insert_with(|| {
+                        let color = library.get(&mtl).copied().unwrap_or_else(|| {
+                            log::warn!("OBJ: material {:?} is not defined in any mtllib", mtl);
+                            DEFAULT_COLOR
+                        });
+                        materials.push(Material { name: mtl, color });
+                        materials.len() as u32 - 1
+                    }))
+                };
+            }
+            "mtllib" => {
+                // File names may contain spaces; try the whole rest first.
+                let rest: Vec<&str> = tokens.collect();
+                let whole = rest.join(" ");
+                let candidates = if mtl_path(base_dir, &whole).is_some_and(|p| p.is_file()) {
+                    vec![whole]
+                } else {
+                    rest.iter().map(|s| s.to_string()).collect()
+                };
+                for file in candidates {
+                    let Some(path) = mtl_path(base_dir, &file) else {
+                        log::warn!("OBJ: ignoring mtllib {:?} outside the model folder", file);
+                        continue;
+                    };
+                    match read_mtl(&path) {
+                        Ok(colors) => library.extend(colors),
+                        Err(e) => log::warn!("OBJ: cannot read mtllib {:?}: {}", path, e),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    progress.progress(Stage::Parsing, 1.0);
+
+    let degenerate: u64 = objects.iter().map(|o| o.degenerate).sum();
+    objects.retain(|o| !o.corners.is_empty());
+    if objects.is_empty() {
+        return Err(ObjError::Empty { degenerate }.into());
+    }
+    if materials.is_empty() {
+        for object in &mut objects {
+            object.materials.clear();
+        }
+    }
+    Ok(ParsedObjects { objects, materials })
+}
+
+/// Resolves a 1-based (or negative, relative) vertex reference.
+fn resolve(positions: &[[f32; 3]], index: i64, line: usize) -> Result<[f32; 3], ObjError> {
+    let resolved = if index < 0 {
+        positions.len() as i64 + index
+    } else {
+        index - 1
+    };
+    usize::try_from(resolved)
+        .ok()
+        .and_then(|i| positions.get(i))
+        .copied()
+        .ok_or(ObjError::BadIndex { line, index })
+}
+
+/// Reads `newmtl` colours from a material library: `Kd` as RGB, `d` (or
+/// `1 - Tr`) as alpha.
+/// `name` inside `base_dir`, if it is a plain relative path that stays there.
+fn mtl_path(base_dir: &Path, name: &str) -> Option<PathBuf> {
+    let relative = Path::new(name);
+    let plain = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    (plain && !name.is_empty()).then(|| base_dir.join(relative))
+}
+
+fn read_mtl(path: &Path) -> std::io::Result<HashMap<String, [u8; 4]>> {
+    // Checked before opening: opening a FIFO would block the worker.
+    if !std::fs::metadata(path)?.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "not a regular file",
+        ));
+    }
+    let reader = BufReader::new(File::open(path)?);
+    let mut colors = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in reader.split(b'\n') {
+        let line = line?;
+        let line = String::from_utf8_lossy(&line);
+        let mut tokens = line.split_ascii_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let values: Vec<f32> = match keyword {
+            "Kd" | "d" | "Tr" => tokens.filter_map(|t| t.parse().ok()).collect(),
+            "newmtl" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                colors.insert(name.clone(), DEFAULT_COLOR);
+                current = Some(name);
+                continue;
+            }
+            _ => continue,
+        };
+        let Some(color) = current.as_ref().and_then(|n| colors.get_mut(n)) else {
+            continue;
+        };
+        match (keyword, values.as_slice()) {
+            ("Kd", [r, g, b, ..]) => {
+                color[0] = channel(*r);
+                color[1] = channel(*g);
+                color[2] = channel(*b);
+            }
+            ("d", [d, ..]) => color[3] = channel(*d),
+            ("Tr", [tr, ..]) => color[3] = channel(1.0 - tr),
+            _ => {}
+        }
+    }
+    Ok(colors)
+}
+
+fn channel(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
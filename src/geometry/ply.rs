@@ -0,0 +1,476 @@
+//! PLY (Stanford Polygon File) Reader
+//!
+//! Governance: .agent/skills/geometry_governance/SKILL.md
+//!
+//! A PLY file is a text header describing elements and their typed
+//! properties, followed by the element data as ASCII, binary little-endian
+//! or binary big-endian. Only `vertex` positions (`x`, `y`, `z`) and `face`
+//! index lists (`vertex_indices` / `vertex_index`) are used; every other
+//! property and element is read and discarded so the stream stays aligned.
+//! Polygons are ear-clipped like OBJ faces.
+//!
+//! Element counts come from the header and are untrusted: buffers grow as
+//! data is actually read instead of being sized from the counts, and a count
+//! the remaining bytes cannot hold is rejected before reading. Elements
+//! without properties would loop without consuming data and are rejected.
+
+use super::mesh::triangulate;
+use super::stl::{is_degenerate, Triangle};
+use super::ProgressSink;
+use crate::progress::Stage;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Header lines are short; anything longer is not a PLY header.
+const MAX_HEADER_LINES: usize = 1024;
+/// Elements between progress/cancellation checks.
+const PROGRESS_INTERVAL: u64 = 16 * 1024;
+
+/// Why a PLY file was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlyError {
+    /// Missing `ply` magic, unknown format or malformed header line.
+    Header(String),
+    /// The data ends before the header's element counts are satisfied.
+    Truncated {
+        element: String,
+        read: u64,
+        count: u64,
+    },
+    /// Malformed ASCII data (1-based line number within the file).
+    Syntax { line: usize, message: String },
+    /// A face references a vertex that does not exist (1-based face number).
+    BadIndex { face: u64, index: i64 },
+    /// A vertex coordinate is NaN or infinite (1-based vertex number).
+    NonFinite { vertex: u64 },
+    /// No usable triangles remain after skipping degenerate ones.
+    Empty { degenerate: u64 },
+}
+
+impl PlyError {
+    /// Stable identifier recorded as the job's failure reason.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PlyError::Header(_) => "ply_header",
+            PlyError::Truncated { .. } => "ply_truncated",
+            PlyError::Syntax { .. } => "ply_syntax",
+            PlyError::BadIndex { .. } => "ply_bad_index",
+            PlyError::NonFinite { .. } => "ply_non_finite",
+            PlyError::Empty { .. } => "ply_empty",
+        }
+    }
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlyError::Header(message) => write!(f, "invalid PLY header: {}", message),
+            PlyError::Truncated {
+                element,
+                read,
+                count,
+            } => write!(
+                f,
+                "truncated PLY: read {} of {} {} elements",
+                read, count, element
+            ),
+            PlyError::Syntax { line, message } => write!(f, "PLY line {}: {}", line, message),
+            PlyError::BadIndex { face, index } => {
+                write!(f, "face {} references missing vertex {}", face, index)
+            }
+            PlyError::NonFinite { vertex } => {
+                write!(f, "vertex {} has a NaN or infinite coordinate", vertex)
+            }
+            PlyError::Empty { degenerate: 0 } => write!(f, "PLY contains no faces"),
+            PlyError::Empty { degenerate } => write!(
+                f,
+                "PLY contains no usable faces ({} degenerate triangles skipped)",
+                degenerate
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PlyError {}
+
+/// Corner list produced by [`parse_ply`].
+pub struct ParsedPly {
+    /// Three corners per triangle, in file order.
+    pub corners: Vec<[f32; 3]>,
+    /// Zero-area triangles that were skipped.
+    pub degenerate: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Property {
+    Scalar {
+        name: String,
+        kind: Scalar,
+    },
+    List {
+        name: String,
+        count: Scalar,
+        item: Scalar,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: u64,
+    properties: Vec<Property>,
+}
+
+/// Reads the vertices and faces of a PLY file as a flat corner list.
+pub fn parse_ply(path: &Path, progress: &dyn ProgressSink) -> anyhow::Result<ParsedPly> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len().max(1);
+    let mut input = Input {
+        reader: BufReader::new(file),
+        consumed: 0,
+        line: 0,
+        tokens: Vec::new(),
+        next_token: 0,
+    };
+    let (encoding, elements) = read_header(&mut input)?;
+    check_counts(&elements, encoding, len.saturating_sub(input.consumed))?;
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut corners: Vec<[f32; 3]> = Vec::new();
+    let mut degenerate = 0u64;
+    let mut values: Vec<f64> = Vec::new();
+    let mut polygon: Vec<[f32; 3]> = Vec::new();
+
+    for element in &elements {
+        let xyz = ["x", "y", "z"].map(|axis| {
+            element
+                .properties
+                .iter()
+                .position(|p| matches!(p, Property::Scalar { name, .. } if name == axis))
+        });
+        let indices = element.properties.iter().position(|p| {
+            matches!(p, Property::List { name, .. } if name == "vertex_indices" || name == "vertex_index")
+        });
+
+        for n in 0..element.count {
+            if n % PROGRESS_INTERVAL == 0 {
+                progress.progress(Stage::Parsing, input.consumed as f64 / len as f64);
+                progress.check_cancelled()?;
+            }
+            // Position-only vertex data is the common case; other elements
+            // are still read property by property to stay aligned.
+            let mut list_values: Vec<i64> = Vec::new();
+            values.clear();
+            for (idx, property) in element.properties.iter().enumerate() {
+                let read = match property {
+                    Property::Scalar { kind, .. } => input.scalar(encoding, *kind).map(|v| {
+                        values.push(v);
+                    }),
+                    Property::List { count, item, .. } => {
+                        let wanted = Some(idx) == indices;
+                        values.push(0.0);
+                        input.scalar(encoding, *count).and_then(|count| {
+                            for _ in 0..count as u64 {
+                                let v = input.scalar(encoding, *item)?;
+                                if wanted {
+                                    list_values.push(v as i64);
+                                }
+                            }
+                            Ok(())
+                        })
+                    }
+                };
+                read.map_err(|e| match e {
+                    InputError::Eof => PlyError::Truncated {
+                        element: element.name.clone(),
+                        read: n,
+                        count: element.count,
+                    }
+                    .into(),
+                    InputError::Syntax(message) => anyhow::Error::from(PlyError::Syntax {
+                        line: input.line,
+                        message,
+                    }),
+                    InputError::Io(e) => e.into(),
+                })?;
+            }
+
+            if element.name == "vertex" {
+                let [Some(x), Some(y), Some(z)] = xyz else {
+                    return Err(PlyError::Header("vertex element lacks x/y/z".to_string()).into());
+                };
+                let v = [values[x] as f32, values[y] as f32, values[z] as f32];
+                if v.iter().any(|c| !c.is_finite()) {
+                    return Err(PlyError::NonFinite { vertex: n + 1 }.into());
+                }
+                positions.push(v);
+            } else if element.name == "face" && indices.is_some() {
+                polygon.clear();
+                for &index in &list_values {
+                    let corner = usize::try_from(index)
+                        .ok()
+                        .and_then(|i| positions.get(i))
+                        .ok_or(PlyError::BadIndex { face: n + 1, index })?;
+                    polygon.push(*corner);
+                }
+                if polygon.len() < 3 {
+                    degenerate += 1;
+                    continue;
+                }
+                for [a, b, c] in triangulate(&polygon) {
+                    let tri: Triangle = [polygon[a], polygon[b], polygon[c]];
+                    if is_degenerate(&tri) {
+                        degenerate += 1;
+                        continue;
+                    }
+                    corners.extend_from_slice(&tri);
+                }
+            }
+        }
+    }
+    progress.progress(Stage::Parsing, 1.0);
+
+    if corners.is_empty() {
+        return Err(PlyError::Empty { degenerate }.into());
+    }
+    Ok(ParsedPly {
+        corners,
+        degenerate,
+    })
+}
+
+fn read_header(input: &mut Input) -> anyhow::Result<(Encoding, Vec<Element>)> {
+    let header_error = |message: String| anyhow::Error::from(PlyError::Header(message));
+    if input.header_line()?.as_deref() != Some("ply") {
+        return Err(header_error("missing 'ply' magic".to_string()));
+    }
+
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for _ in 0..MAX_HEADER_LINES {
+        let Some(line) = input.header_line()? else {
+            return Err(header_error("missing end_header".to_string()));
+        };
+        let tokens: Vec<&str> = line.split_ascii_whitespace().collect();
+        match tokens.as_slice() {
+            ["end_header"] => {
+                let encoding = encoding.ok_or_else(|| header_error("missing format".into()))?;
+                if let Some(element) = elements
+                    .iter()
+                    .find(|e| e.count > 0 && e.properties.is_empty())
+                {
+                    return Err(header_error(format!(
+                        "element {:?} has no properties",
+                        element.name
+                    )));
+                }
+                return Ok((encoding, elements));
+            }
+            ["format", format, _version] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::LittleEndian,
+                    "binary_big_endian" => Encoding::BigEndian,
+                    other => return Err(header_error(format!("unknown format {:?}", other))),
+                });
+            }
+            ["element", name, count] => {
+                let count = count
+                    .parse()
+                    .map_err(|_| header_error(format!("invalid element count {:?}", count)))?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            ["property", "list", count, item, name] => {
+                let (Some(count), Some(item)) = (Scalar::parse(count), Scalar::parse(item)) else {
+                    return Err(header_error(format!("invalid list property {:?}", line)));
+                };
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| header_error("property before element".into()))?;
+                element.properties.push(Property::List {
+                    name: name.to_string(),
+                    count,
+                    item,
+                });
+            }
+            ["property", kind, name] => {
+                let kind = Scalar::parse(kind)
+                    .ok_or_else(|| header_error(format!("unknown property type {:?}", kind)))?;
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| header_error("property before element".into()))?;
+                element.properties.push(Property::Scalar {
+                    name: name.to_string(),
+                    kind,
+                });
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(header_error(format!("unexpected line {:?}", line))),
+        }
+    }
+    Err(header_error("header too long".to_string()))
+}
+
+/// Rejects element counts that cannot fit in `remaining` body bytes, given
+/// the smallest possible encoding of one element.
+fn check_counts(elements: &[Element], encoding: Encoding, remaining: u64) -> Result<(), PlyError> {
+    let mut needed = 0u64;
+    for element in elements {
+        let min_size: u64 = match encoding {
+            // One digit per value; separators come on top.
+            Encoding::Ascii => element.properties.len() as u64,
+            _ => element
+                .properties
+                .iter()
+                .map(|p| match p {
+                    Property::Scalar { kind, .. } => kind.size() as u64,
+                    Property::List { count, .. } => count.size() as u64,
+                })
+                .sum(),
+        };
+        let available = remaining.saturating_sub(needed) / min_size.max(1);
+        if element.count > available {
+            return Err(PlyError::Truncated {
+                element: element.name.clone(),
+                read: available,
+                count: element.count,
+            });
+        }
+        needed += element.count * min_size;
+    }
+    Ok(())
+}
+
+enum InputError {
+    Eof,
+    Syntax(String),
+    Io(io::Error),
+}
+
+impl From<io::Error> for InputError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            InputError::Eof
+        } else {
+            InputError::Io(e)
+        }
+    }
+}
+
+/// Data stream after the header: typed binary reads, or whitespace-separated
+/// ASCII tokens (elements may span or share lines).
+struct Input {
+    reader: BufReader<File>,
+    consumed: u64,
+    line: usize,
+    tokens: Vec<String>,
+    next_token: usize,
+}
+
+impl Input {
+    /// Header lines are decoded lossily: comments may be in any encoding.
+    fn header_line(&mut self) -> io::Result<Option<String>> {
+        let line = self.raw_line()?;
+        Ok(line.map(|line| String::from_utf8_lossy(&line).trim().to_string()))
+    }
+
+    fn raw_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut line = Vec::new();
+        let read = self.reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.consumed += read as u64;
+        self.line += 1;
+        Ok(Some(line))
+    }
+
+    fn scalar(&mut self, encoding: Encoding, kind: Scalar) -> Result<f64, InputError> {
+        if encoding == Encoding::Ascii {
+            let token = self.token()?;
+            return token
+                .parse::<f64>()
+                .map_err(|_| InputError::Syntax(format!("invalid number {:?}", token)));
+        }
+        let mut buf = [0u8; 8];
+        let bytes = &mut buf[..kind.size()];
+        self.reader.read_exact(bytes)?;
+        self.consumed += bytes.len() as u64;
+        if encoding == Encoding::BigEndian {
+            bytes.reverse();
+        }
+        Ok(match kind {
+            Scalar::I8 => bytes[0] as i8 as f64,
+            Scalar::U8 => bytes[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(buf),
+        })
+    }
+
+    fn token(&mut self) -> Result<String, InputError> {
+        while self.next_token >= self.tokens.len() {
+            let line = self.raw_line()?.ok_or(InputError::Eof)?;
+            self.tokens = String::from_utf8_lossy(&line)
+                .split_ascii_whitespace()
+                .map(str::to_string)
+                .collect();
+            self.next_token = 0;
+        }
+        self.next_token += 1;
+        Ok(std::mem::take(&mut self.tokens[self.next_token - 1]))
+    }
+}
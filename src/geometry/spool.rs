@@ -0,0 +1,210 @@
+//! Bounded-Memory Mesh Spooling
+//!
+//! Governance: .agent/skills/geometry_governance/SKILL.md (Memory Management)
+//!
+//! Large STLs are streamed triangle by triangle: vertices are deduplicated
+//! incrementally and written, together with the index triples, to two spool
+//! files in the spool directory (`SPOOL_DIR`, on disk rather than the tmpfs
+//! working dir, since spooling exists to keep the mesh out of RAM). Only the dedup map lives in memory, and it
+//! stops growing once it reaches the memory budget. Vertices seen after that
+//! point are written as new vertices, which costs file size but never
+//! correctness.
+
+use super::mesh::{vertex_key, Bounds, MeshSource};
+use super::stl::{StlReader, PROGRESS_INTERVAL};
+use super::ProgressSink;
+use crate::progress::Stage;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Approximate heap cost of one dedup map entry (key, value, hash table
+/// overhead and growth slack).
+const MAP_ENTRY_COST: u64 = 40;
+
+static SPOOL_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Indexed mesh stored in spool files. The files are removed on drop.
+pub struct SpooledMesh {
+    name: String,
+    vertex_count: usize,
+    triangle_count: usize,
+    degenerate: u64,
+    /// Tracked while spooling so layout does not need another pass.
+    bounds: Option<Bounds>,
+    vertex_path: PathBuf,
+    triangle_path: PathBuf,
+}
+
+impl Drop for SpooledMesh {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.vertex_path);
+        let _ = std::fs::remove_file(&self.triangle_path);
+    }
+}
+
+impl SpooledMesh {
+    /// Streams `path` into spool files below `spool_dir`, keeping the dedup map
+    /// under roughly `budget` bytes.
+    pub fn from_stl(
+        path: &Path,
+        name: String,
+        spool_dir: &Path,
+        budget: u64,
+        progress: &dyn ProgressSink,
+    ) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(spool_dir)?;
+        let seq = SPOOL_SEQ.fetch_add(1, Ordering::Relaxed);
+        let stem = format!("mesh-{}-{}", std::process::id(), seq);
+        // Construct first so the files are cleaned up on every error path.
+        let mut mesh = SpooledMesh {
+            name,
+            vertex_count: 0,
+            triangle_count: 0,
+            degenerate: 0,
+            bounds: None,
+            vertex_path: spool_dir.join(format!("{}.vertices", stem)),
+            triangle_path: spool_dir.join(format!("{}.triangles", stem)),
+        };
+
+        let mut vertices = BufWriter::new(File::create(&mesh.vertex_path)?);
+        let mut triangles = BufWriter::new(File::create(&mesh.triangle_path)?);
+        let max_entries = (budget / MAP_ENTRY_COST) as usize;
+        let mut unique: HashMap<[u32; 3], u32> = HashMap::new();
+        let mut saturated = false;
+
+        let mut reader = StlReader::open(path)?;
+        while let Some(tri) = reader.next_triangle()? {
+            for corner in &tri {
+                let key = vertex_key(corner);
+                let index = match unique.get(&key) {
+                    Some(index) => *index,
+                    None => {
+                        let index = u32::try_from(mesh.vertex_count)
+                            .map_err(|_| anyhow::anyhow!("Mesh exceeds {} vertices", u32::MAX))?;
+                        for coord in corner {
+                            vertices.write_all(&coord.to_le_bytes())?;
+                        }
+                        mesh.vertex_count += 1;
+                        match mesh.bounds.as_mut() {
+                            Some(b) => b.include(*corner),
+                            None => {
+                                mesh.bounds = Some(Bounds {
+                                    min: *corner,
+                                    max: *corner,
+                                })
+                            }
+                        }
+                        if unique.len() < max_entries {
+                            unique.insert(key, index);
+                        } else if !saturated {
+                            saturated = true;
+                            log::warn!(
+                                "Geometry: dedup map for {:?} reached the memory budget ({} MB); \
+                                 remaining vertices are written without merging",
+                                path,
+                                budget / (1024 * 1024)
+                            );
+                        }
+                        index
+                    }
+                };
+                triangles.write_all(&index.to_le_bytes())?;
+            }
+            mesh.triangle_count += 1;
+            if mesh.triangle_count % PROGRESS_INTERVAL == 0 {
+                progress.progress(Stage::Parsing, reader.fraction());
+                progress.check_cancelled()?;
+            }
+        }
+        reader.finish()?;
+        mesh.degenerate = reader.degenerate();
+        vertices.flush()?;
+        triangles.flush()?;
+        progress.progress(Stage::Parsing, 1.0);
+        progress.progress(Stage::Deduplicating, 1.0);
+        Ok(mesh)
+    }
+}
+
+impl SpooledMesh {
+    /// Removes spool files left behind by a conversion that was killed before
+    /// its meshes were dropped. Called once at startup, before any job runs.
+    pub fn clear_stale(spool_dir: &Path) -> io::Result<()> {
+        let entries = match std::fs::read_dir(spool_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if name.starts_with("mesh-")
+                && (name.ends_with(".vertices") || name.ends_with(".triangles"))
+            {
+                std::fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Zero-area triangles skipped while spooling.
+    pub fn degenerate(&self) -> u64 {
+        self.degenerate
+    }
+}
+
+impl MeshSource for SpooledMesh {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.triangle_count
+    }
+
+    fn for_each_vertex(&self, f: &mut dyn FnMut([f32; 3]) -> io::Result<()>) -> io::Result<()> {
+        for_each_record(&self.vertex_path, self.vertex_count, |r| {
+            f([f32_le(r, 0), f32_le(r, 4), f32_le(r, 8)])
+        })
+    }
+
+    fn for_each_triangle(&self, f: &mut dyn FnMut([u32; 3]) -> io::Result<()>) -> io::Result<()> {
+        for_each_record(&self.triangle_path, self.triangle_count, |r| {
+            f([u32_le(r, 0), u32_le(r, 4), u32_le(r, 8)])
+        })
+    }
+
+    fn bounds(&self) -> io::Result<Option<Bounds>> {
+        Ok(self.bounds)
+    }
+}
+
+/// Reads `count` fixed 12-byte records from a spool file.
+fn for_each_record(
+    path: &Path,
+    count: usize,
+    mut f: impl FnMut(&[u8; 12]) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut reader = BufReader::with_capacity(256 * 1024, File::open(path)?);
+    let mut record = [0u8; 12];
+    for _ in 0..count {
+        reader.read_exact(&mut record)?;
+        f(&record)?;
+    }
+    Ok(())
+}
+
+fn f32_le(bytes: &[u8; 12], at: usize) -> f32 {
+    f32::from_bits(u32_le(bytes, at))
+}
+
+fn u32_le(bytes: &[u8; 12], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
@@ -0,0 +1,441 @@
+//! STL Reader
+//!
+//! Governance: .agent/skills/stl_specification/SKILL.md
+//!
+//! Binary STL layout: 80-byte header, `u32` triangle count, then 50 bytes per
+//! triangle (normal, three vertices as little-endian `f32`, attribute word).
+//!
+//! ASCII STL is a `solid ... facet normal ... outer loop / vertex x y z ...`
+//! text stream. Many binary exporters also start their header with `solid`,
+//! so the keyword alone is not enough to pick a parser; see [`detect_format`].
+//!
+//! Malformed input is reported as [`StlError`] before anything is allocated
+//! from untrusted counts: the binary triangle count must match the file size
+//! exactly, and every coordinate must be finite. Degenerate (zero-area)
+//! triangles are skipped and counted rather than failing the file.
+
+use super::ProgressSink;
+use crate::progress::Stage;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const HEADER_LEN: usize = 80;
+const TRIANGLE_LEN: usize = 50;
+/// Bytes inspected when deciding whether a `solid` file is really text.
+const SNIFF_LEN: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StlFormat {
+    Binary,
+    Ascii,
+}
+
+/// One triangle as three corner positions.
+pub type Triangle = [[f32; 3]; 3];
+
+/// Why an STL file was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StlError {
+    /// Shorter than the 84-byte binary header.
+    TooShort { len: u64 },
+    /// Fewer bytes than the header's triangle count requires.
+    Truncated {
+        count: u32,
+        expected: u64,
+        actual: u64,
+    },
+    /// More bytes than the header's triangle count accounts for.
+    TrailingData {
+        count: u32,
+        expected: u64,
+        actual: u64,
+    },
+    /// A coordinate is NaN or infinite (1-based triangle number).
+    NonFinite { triangle: u64 },
+    /// Malformed ASCII STL.
+    Syntax { line: usize, message: String },
+    /// No usable triangles remain after skipping degenerate ones.
+    Empty { degenerate: u64 },
+}
+
+impl StlError {
+    /// Stable identifier recorded as the job's failure reason.
+    pub fn code(&self) -> &'static str {
+        match self {
+            StlError::TooShort { .. } => "stl_too_short",
+            StlError::Truncated { .. } => "stl_truncated",
+            StlError::TrailingData { .. } => "stl_trailing_data",
+            StlError::NonFinite { .. } => "stl_non_finite",
+            StlError::Syntax { .. } => "stl_syntax",
+            StlError::Empty { .. } => "stl_empty",
+        }
+    }
+}
+
+impl fmt::Display for StlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StlError::TooShort { len } => {
+                write!(f, "file is {} bytes, shorter than the 84-byte STL header", len)
+            }
+            StlError::Truncated {
+                count,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "truncated binary STL: header declares {} triangles ({} bytes) but the file has {} bytes",
+                count, expected, actual
+            ),
+            StlError::TrailingData {
+                count,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "binary STL has {} unexpected trailing bytes after {} declared triangles ({} of {} bytes used)",
+                actual - expected,
+                count,
+                expected,
+                actual
+            ),
+            StlError::NonFinite { triangle } => {
+                write!(f, "triangle {} has a NaN or infinite coordinate", triangle)
+            }
+            StlError::Syntax { line, message } => write!(f, "ASCII STL line {}: {}", line, message),
+            StlError::Empty { degenerate: 0 } => write!(f, "STL contains no triangles"),
+            StlError::Empty { degenerate } => write!(
+                f,
+                "STL contains no usable triangles ({} degenerate triangles skipped)",
+                degenerate
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StlError {}
+
+/// Corner list produced by [`parse_stl`].
+pub struct ParsedStl {
+    /// Three corners per triangle, in file order.
+    pub corners: Vec<[f32; 3]>,
+    /// Zero-area triangles that were skipped.
+    pub degenerate: u64,
+}
+
+/// Reads every triangle of an STL (binary or ASCII) as a flat list of corner
+/// positions (three per triangle, in file order).
+pub fn parse_stl(path: &Path, progress: &dyn ProgressSink) -> anyhow::Result<ParsedStl> {
+    let mut reader = StlReader::open(path)?;
+    // Safe to trust: `open` checked the count against the file size.
+    let mut corners = Vec::with_capacity(reader.triangle_hint() * 3);
+    let mut n = 0usize;
+    while let Some(tri) = reader.next_triangle()? {
+        corners.extend_from_slice(&tri);
+        n += 1;
+        if n % PROGRESS_INTERVAL == 0 {
+            progress.progress(Stage::Parsing, reader.fraction());
+            progress.check_cancelled()?;
+        }
+    }
+    reader.finish()?;
+    progress.progress(Stage::Parsing, 1.0);
+    Ok(ParsedStl {
+        corners,
+        degenerate: reader.degenerate(),
+    })
+}
+
+/// Triangles between progress/cancellation checks.
+pub(super) const PROGRESS_INTERVAL: usize = 16 * 1024;
+
+/// Pull-based STL reader that yields one triangle at a time, so callers can
+/// process meshes larger than memory.
+pub struct StlReader {
+    source: Source,
+    len: u64,
+    /// Triangles returned or skipped so far (1-based numbering in errors).
+    seen: u64,
+    degenerate: u64,
+}
+
+enum Source {
+    Binary {
+        reader: BufReader<File>,
+        count: u64,
+        read: u64,
+    },
+    Ascii {
+        reader: BufReader<File>,
+        line: Vec<u8>,
+        line_no: usize,
+        consumed: u64,
+        facet: Vec<[f32; 3]>,
+        pending: VecDeque<Triangle>,
+    },
+}
+
+impl StlReader {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let format = detect_format(&mut file, len)?;
+        file.seek(SeekFrom::Start(0))?;
+        log::debug!("STL: {:?} detected as {:?}", path, format);
+
+        let mut reader = BufReader::new(file);
+        let source = match format {
+            StlFormat::Binary => {
+                if len < (HEADER_LEN + 4) as u64 {
+                    return Err(StlError::TooShort { len }.into());
+                }
+                let mut header = [0u8; HEADER_LEN + 4];
+                reader.read_exact(&mut header)?;
+                let count = u32::from_le_bytes([header[80], header[81], header[82], header[83]]);
+                let expected = (HEADER_LEN + 4) as u64 + count as u64 * TRIANGLE_LEN as u64;
+                if len < expected {
+                    return Err(StlError::Truncated {
+                        count,
+                        expected,
+                        actual: len,
+                    }
+                    .into());
+                }
+                if len > expected {
+                    return Err(StlError::TrailingData {
+                        count,
+                        expected,
+                        actual: len,
+                    }
+                    .into());
+                }
+                Source::Binary {
+                    reader,
+                    count: count as u64,
+                    read: 0,
+                }
+            }
+            StlFormat::Ascii => Source::Ascii {
+                reader,
+                line: Vec::new(),
+                line_no: 0,
+                consumed: 0,
+                facet: Vec::with_capacity(3),
+                pending: VecDeque::new(),
+            },
+        };
+        Ok(Self {
+            source,
+            len,
+            seen: 0,
+            degenerate: 0,
+        })
+    }
+
+    /// Number of zero-area triangles skipped so far.
+    pub fn degenerate(&self) -> u64 {
+        self.degenerate
+    }
+
+    /// Call after the last triangle; fails if nothing usable was read.
+    pub fn finish(&self) -> Result<(), StlError> {
+        if self.seen == self.degenerate {
+            return Err(StlError::Empty {
+                degenerate: self.degenerate,
+            });
+        }
+        Ok(())
+    }
+
+    /// Expected number of triangles (exact for binary, 0 for ASCII).
+    pub fn triangle_hint(&self) -> usize {
+        match &self.source {
+            Source::Binary { count, .. } => *count as usize,
+            Source::Ascii { .. } => 0,
+        }
+    }
+
+    /// Share of the input consumed so far, for progress reporting.
+    pub fn fraction(&self) -> f64 {
+        match &self.source {
+            Source::Binary { count, read, .. } => *read as f64 / (*count).max(1) as f64,
+            Source::Ascii { consumed, .. } => *consumed as f64 / self.len.max(1) as f64,
+        }
+    }
+
+    /// Returns the next non-degenerate triangle, or `None` at end of input.
+    pub fn next_triangle(&mut self) -> anyhow::Result<Option<Triangle>> {
+        while let Some(tri) = self.read_raw()? {
+            self.seen += 1;
+            if tri.iter().flatten().any(|c| !c.is_finite()) {
+                return Err(StlError::NonFinite {
+                    triangle: self.seen,
+                }
+                .into());
+            }
+            if is_degenerate(&tri) {
+                self.degenerate += 1;
+                continue;
+            }
+            return Ok(Some(tri));
+        }
+        Ok(None)
+    }
+
+    fn read_raw(&mut self) -> anyhow::Result<Option<Triangle>> {
+        match &mut self.source {
+            Source::Binary {
+                reader,
+                count,
+                read,
+            } => {
+                if *read >= *count {
+                    return Ok(None);
+                }
+                let mut buf = [0u8; TRIANGLE_LEN];
+                reader.read_exact(&mut buf)?;
+                *read += 1;
+                let mut tri = [[0f32; 3]; 3];
+                for (corner, slot) in tri.iter_mut().enumerate() {
+                    let base = 12 + corner * 12;
+                    *slot = [
+                        f32_le(&buf[base..base + 4]),
+                        f32_le(&buf[base + 4..base + 8]),
+                        f32_le(&buf[base + 8..base + 12]),
+                    ];
+                }
+                Ok(Some(tri))
+            }
+            Source::Ascii {
+                reader,
+                line,
+                line_no,
+                consumed,
+                facet,
+                pending,
+            } => loop {
+                if let Some(tri) = pending.pop_front() {
+                    return Ok(Some(tri));
+                }
+                line.clear();
+                let read = reader.read_until(b'\n', line)?;
+                if read == 0 {
+                    return Ok(None);
+                }
+                *line_no += 1;
+                *consumed += read as u64;
+                // Solid names are free text in any encoding.
+                parse_ascii_line(&String::from_utf8_lossy(line), *line_no, facet, pending)?;
+            },
+        }
+    }
+}
+
+/// Handles one ASCII STL line. Loops with more than three vertices (emitted by
+/// a few CAD exporters) are fan-triangulated; multiple `solid` blocks are
+/// simply concatenated.
+fn parse_ascii_line(
+    line: &str,
+    line_no: usize,
+    facet: &mut Vec<[f32; 3]>,
+    out: &mut VecDeque<Triangle>,
+) -> anyhow::Result<()> {
+    let mut tokens = line.split_ascii_whitespace();
+    let Some(keyword) = tokens.next() else {
+        return Ok(());
+    };
+    match keyword.to_ascii_lowercase().as_str() {
+        "vertex" => {
+            let mut v = [0f32; 3];
+            for slot in &mut v {
+                let token = tokens.next().ok_or_else(|| StlError::Syntax {
+                    line: line_no,
+                    message: "vertex needs 3 coordinates".to_string(),
+                })?;
+                *slot = token.parse().map_err(|_| StlError::Syntax {
+                    line: line_no,
+                    message: format!("invalid number {:?}", token),
+                })?;
+            }
+            facet.push(v);
+        }
+        "endloop" | "endfacet" => {
+            if facet.len() >= 3 {
+                for i in 1..facet.len() - 1 {
+                    out.push_back([facet[0], facet[i], facet[i + 1]]);
+                }
+            } else if !facet.is_empty() {
+                return Err(StlError::Syntax {
+                    line: line_no,
+                    message: format!("facet has only {} vertices", facet.len()),
+                }
+                .into());
+            }
+            facet.clear();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Decides between the binary and ASCII parser.
+///
+/// A file is ASCII only if it starts with `solid`, its size does not match the
+/// binary layout implied by the triangle count (`84 + 50 * n`), and the line
+/// after the `solid` line starts with `facet` or `endsolid`. This keeps binary
+/// files whose header happens to begin with `solid` on the binary path, while
+/// solid names may contain any bytes (`solid Würfel`).
+pub fn detect_format<R: Read + Seek>(reader: &mut R, len: u64) -> anyhow::Result<StlFormat> {
+    let mut head = vec![0u8; SNIFF_LEN.min(len as usize)];
+    reader.seek(SeekFrom::Start(0))?;
+    reader.read_exact(&mut head)?;
+
+    let trimmed = head
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .map_or(&head[..0], |start| &head[start..]);
+    if !trimmed.starts_with(b"solid") {
+        return Ok(StlFormat::Binary);
+    }
+
+    if head.len() >= HEADER_LEN + 4 {
+        let count = u32::from_le_bytes([head[80], head[81], head[82], head[83]]) as u64;
+        if (HEADER_LEN as u64 + 4) + count * TRIANGLE_LEN as u64 == len {
+            return Ok(StlFormat::Binary);
+        }
+    }
+
+    let Some(eol) = trimmed.iter().position(|&b| b == b'\n') else {
+        return Ok(StlFormat::Binary);
+    };
+    let next = trimmed[eol..]
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .map_or(&trimmed[..0], |start| &trimmed[eol + start..]);
+    let keyword =
+        |word: &[u8]| next.len() >= word.len() && next[..word.len()].eq_ignore_ascii_case(word);
+    if keyword(b"facet") || keyword(b"endsolid") {
+        Ok(StlFormat::Ascii)
+    } else {
+        Ok(StlFormat::Binary)
+    }
+}
+
+/// True if the triangle has zero area (repeated or collinear corners).
+pub(super) fn is_degenerate([a, b, c]: &Triangle) -> bool {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    cross == [0.0; 3]
+}
+
+fn f32_le(bytes: &[u8]) -> f32 {
+    f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
@@ -1,18 +1,58 @@
+mod archive;
+mod config;
+mod datapackage;
+mod geometry;
 mod hal;
+mod pipeline;
+mod preview;
+mod progress;
+mod project;
+mod queue;
+mod threemf;
+mod watcher;
 mod web;
 
+use std::sync::Arc;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize logger
     env_logger::init();
     log::info!("Starting Manyfold Processor v0.3.0");
 
+    let config = config::Config::from_env();
+
     // Initialize Hardware Abstraction Layer
     let (_image_processor, _inference_engine) = hal::select_hal();
 
+    // Open the persistent job queue (recovers jobs interrupted by a crash)
+    let events = progress::EventBus::new();
+    let queue = Arc::new(queue::JobQueue::open(
+        &config.config_dir,
+        config.max_attempts,
+        config.job_retention,
+        events,
+    )?);
+
+    // Spool files of a conversion killed mid-way outlive the process on disk
+    if let Err(e) = geometry::SpooledMesh::clear_stale(&config.spool_dir) {
+        log::warn!("Cannot clear spool dir {:?}: {}", config.spool_dir, e);
+    }
+
+    // Start the input watcher; settled items are enqueued as jobs
+    let _watcher = watcher::start(&config, Arc::clone(&queue))?;
+
+    // Start the processing worker
+    let worker_handle = tokio::spawn(pipeline::run_worker(Arc::clone(&queue), config.clone()));
+
     // Start the web server in a background task
-    let web_handle = tokio::spawn(async {
-        if let Err(e) = web::start_web_server().await {
+    let state = web::AppState {
+        queue: Arc::clone(&queue),
+        input_dir: config.input_dir.clone(),
+    };
+    let web_port = config.web_port;
+    let web_handle = tokio::spawn(async move {
+        if let Err(e) = web::start_web_server(state, web_port).await {
             log::error!("Web server failed: {}", e);
         }
     });
@@ -20,7 +60,7 @@ async fn main() -> anyhow::Result<()> {
     log::info!("Manyfold Processor is running. Press Ctrl+C to stop.");
 
     // Wait for the web server (or other tasks) to finish
-    let _ = tokio::join!(web_handle);
+    let _ = tokio::join!(web_handle, worker_handle);
 
     Ok(())
 }
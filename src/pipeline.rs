@@ -0,0 +1,1007 @@
+//! Processing Pipeline (Router + Worker)
+//!
+//! Governance: .agent/skills/architectural_guidelines/SKILL.md (Level 3: Processing Pipeline)
+//!
+//! A single worker drains the [`JobQueue`] one job at a time. Jobs are strictly
+//! serial so Tier 3 devices never hold two large inputs in flight; the heavy
+//! lifting runs on the blocking thread pool to keep the web server responsive.
+
+use crate::archive::{self, ArchiveError, ArchiveFormat, ArchiveLimits};
+use crate::config::Config;
+use crate::datapackage::{self, title_from_name, Datapackage};
+use crate::geometry::{self, AmfError, ConvertOptions, ObjError, PlyError, ProgressSink, StlError};
+use crate::preview::{self, PreviewMatch};
+use crate::progress::{JobEvent, Stage};
+use crate::project::{self, FileKind, ModelGroup};
+use crate::queue::{Job, JobFailure, JobQueue, LogLevel};
+use crate::threemf::{self, PackageInfo, ThreeMfError};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Routing decision for a top-level input item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Directory,
+    Stl,
+    Obj,
+    Ply,
+    Amf,
+    ThreeMf,
+    Archive,
+    Other,
+}
+
+impl fmt::Display for InputKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InputKind::Directory => "directory",
+            InputKind::Stl => "STL",
+            InputKind::Obj => "OBJ",
+            InputKind::Ply => "PLY",
+            InputKind::Amf => "AMF",
+            InputKind::ThreeMf => "3MF",
+            InputKind::Archive => "archive",
+            InputKind::Other => "unsupported",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Determines how an input item is handled (File vs Directory vs Archive).
+pub fn classify(path: &Path) -> InputKind {
+    if path.is_dir() {
+        return InputKind::Directory;
+    }
+    if ArchiveFormat::detect(path).is_some() {
+        return InputKind::Archive;
+    }
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let ext = name.rsplit('.').next().unwrap_or_default();
+    match ext {
+        "stl" => InputKind::Stl,
+        "obj" => InputKind::Obj,
+        "ply" => InputKind::Ply,
+        "amf" => InputKind::Amf,
+        "3mf" => InputKind::ThreeMf,
+        _ => InputKind::Other,
+    }
+}
+
+/// Handle given to job handlers for logging, progress and cooperative cancellation.
+pub struct JobContext {
+    pub job: Job,
+    queue: Arc<JobQueue>,
+    last_progress: Mutex<Option<(Stage, u8)>>,
+}
+
+impl JobContext {
+    fn new(job: Job, queue: Arc<JobQueue>) -> Self {
+        Self {
+            job,
+            queue,
+            last_progress: Mutex::new(None),
+        }
+    }
+
+    /// Publishes a progress event. Repeated calls with the same stage and whole
+    /// percentage are dropped, so handlers may call this per triangle.
+    pub fn progress(&self, stage: Stage, fraction: f64, detail: Option<&str>) {
+        let percent = (fraction.clamp(0.0, 1.0) * 100.0) as u8;
+        {
+            let mut last = self.last_progress.lock().unwrap_or_else(|e| e.into_inner());
+            if *last == Some((stage, percent)) {
+                return;
+            }
+            *last = Some((stage, percent));
+        }
+        self.queue.events().publish(JobEvent::Progress {
+            job_id: self.job.id,
+            stage,
+            percent,
+            detail: detail.map(str::to_string),
+        });
+    }
+
+    pub fn log(&self, level: LogLevel, message: impl Into<String>) {
+        self.queue.log(self.job.id, level, message);
+    }
+
+    /// Returns an error if the job was cancelled through the API. Handlers call
+    /// this between stages; the error unwinds the job as `cancelled`.
+    pub fn check_cancelled(&self) -> anyhow::Result<()> {
+        if self.queue.is_cancel_requested(self.job.id) {
+            anyhow::bail!("Cancelled by user");
+        }
+        Ok(())
+    }
+}
+
+impl ProgressSink for JobContext {
+    fn progress(&self, stage: Stage, fraction: f64) {
+        JobContext::progress(self, stage, fraction, None);
+    }
+
+    fn check_cancelled(&self) -> anyhow::Result<()> {
+        JobContext::check_cancelled(self)
+    }
+}
+
+/// Runs forever, processing one job at a time.
+pub async fn run_worker(queue: Arc<JobQueue>, config: Config) {
+    log::info!("Pipeline: worker started");
+    let config = Arc::new(config);
+    loop {
+        let job = queue.next().await;
+        let id = job.id;
+        log::info!(
+            "Pipeline: starting job {} for {:?} (attempt {})",
+            id,
+            job.source,
+            job.attempts
+        );
+
+        let worker_config = Arc::clone(&config);
+        let ctx = JobContext::new(job, Arc::clone(&queue));
+        let result = tokio::task::spawn_blocking(move || process(&ctx, &worker_config))
+            .await
+            .unwrap_or_else(|e| Err(anyhow::anyhow!("Worker panicked: {}", e)));
+
+        queue.finish(
+            id,
+            result.map_err(|e| JobFailure {
+                message: format!("{:#}", e),
+                reason: failure_reason(&e),
+            }),
+        );
+    }
+}
+
+/// Maps typed handler errors anywhere in the chain to a stable reason code.
+fn failure_reason(error: &anyhow::Error) -> Option<&'static str> {
+    error.chain().find_map(|cause| {
+        None.or_else(|| cause.downcast_ref::<StlError>().map(StlError::code))
+            .or_else(|| cause.downcast_ref::<ObjError>().map(ObjError::code))
+            .or_else(|| cause.downcast_ref::<PlyError>().map(PlyError::code))
+            .or_else(|| cause.downcast_ref::<AmfError>().map(AmfError::code))
+            .or_else(|| cause.downcast_ref::<ThreeMfError>().map(ThreeMfError::code))
+            .or_else(|| cause.downcast_ref::<ArchiveError>().map(ArchiveError::code))
+    })
+}
+
+fn process(ctx: &JobContext, config: &Config) -> anyhow::Result<Vec<PathBuf>> {
+    ctx.check_cancelled()?;
+    let source = &ctx.job.source;
+    if !source.exists() {
+        anyhow::bail!("Source {:?} no longer exists", source);
+    }
+    let kind = classify(source);
+    ctx.progress(Stage::Routing, 1.0, Some(&kind.to_string()));
+    ctx.log(LogLevel::Info, format!("Routed as {} input", kind));
+    match kind {
+        InputKind::Stl | InputKind::Obj | InputKind::Ply | InputKind::Amf => {
+            process_mesh(ctx, config)
+        }
+        InputKind::ThreeMf => process_threemf(ctx, config),
+        InputKind::Archive => process_archive(ctx, config),
+        InputKind::Directory => {
+            let name = source
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            process_directory(ctx, config, source, &name, &|_| None)
+        }
+        _ => anyhow::bail!("No handler available for {} input", kind),
+    }
+}
+
+/// Converts a single loose mesh file (STL, OBJ, PLY, AMF) into `<output>/<slug>/<slug>.3mf`,
+/// staged like a folder (see [`process_directory`]).
+fn process_mesh(ctx: &JobContext, config: &Config) -> anyhow::Result<Vec<PathBuf>> {
+    let source = &ctx.job.source;
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let slug = slugify(&stem);
+    let staging = create_staging(ctx, config)?;
+    let model_dir = staging.path().join(&slug);
+    std::fs::create_dir_all(&model_dir)?;
+
+    let images = sibling_images(source);
+    let root = source.parent().unwrap_or(source);
+    let matches = match_previews(ctx, root, std::slice::from_ref(source), &images, false);
+    let preview = matches.into_iter().next().map(|found| found.image);
+    convert_meshes(
+        ctx,
+        config,
+        std::slice::from_ref(source),
+        &model_dir,
+        &slug,
+        &stem,
+        preview.as_deref(),
+    )?;
+
+    let mut previews = Vec::new();
+    if let Some(preview) = preview {
+        let ext = preview
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let image = PathBuf::from(format!("{}.{}", slug, ext));
+        std::fs::copy(&preview, model_dir.join(&image))?;
+        ctx.log(
+            LogLevel::Info,
+            format!("Copied preview {:?} as {:?}", preview, image),
+        );
+        previews.push(image);
+    }
+
+    let package = datapackage::generate(&model_dir, &slug, &title_from_name(&stem), &previews)?;
+    write_datapackage(ctx, package, &model_dir, &config.output_dir.join(&slug))?;
+    publish_staged(ctx, config, &[(slug, model_dir)])
+}
+
+/// Converts `sources` into `<model_dir>/<slug>.3mf` with `preview` as the
+/// package thumbnail and logs per-object statistics. `name` names the
+/// assembly object in assembly mode.
+fn convert_meshes(
+    ctx: &JobContext,
+    config: &Config,
+    sources: &[PathBuf],
+    model_dir: &Path,
+    slug: &str,
+    name: &str,
+    preview: Option<&Path>,
+) -> anyhow::Result<PathBuf> {
+    let target = model_dir.join(format!("{}.3mf", slug));
+    let partial = model_dir.join(format!("{}.3mf.part", slug));
+    let options = ConvertOptions {
+        memory_budget: config.memory_budget,
+        spool_dir: config.spool_dir.clone(),
+        compression_level: config.compression_level,
+        thumbnail: preview.map(Path::to_path_buf),
+        bed_size: config.bed_size,
+        assembly: config.assembly_output.then(|| name.to_string()),
+        ..ConvertOptions::default()
+    };
+    let summary = geometry::convert_to_3mf(sources, &partial, &options, ctx).inspect_err(|_| {
+        let _ = std::fs::remove_file(&partial);
+    })?;
+    std::fs::rename(&summary.output, &target)?;
+
+    for object in &summary.objects {
+        ctx.log(
+            LogLevel::Info,
+            format!(
+                "Converted {} ({:?}): {} triangles, {} unique vertices",
+                object.name, object.source, object.triangles, object.vertices
+            ),
+        );
+        if object.degenerate > 0 {
+            ctx.log(
+                LogLevel::Warn,
+                format!(
+                    "Skipped {} degenerate triangle(s) in {}",
+                    object.degenerate, object.name
+                ),
+            );
+        }
+    }
+    if let Some(preview) = preview {
+        match &summary.thumbnail_skipped {
+            None => ctx.log(LogLevel::Info, format!("Embedded preview {:?}", preview)),
+            Some(reason) => ctx.log(
+                LogLevel::Warn,
+                format!("Not embedding preview {:?}: {}", preview, reason),
+            ),
+        }
+    }
+    ctx.log(
+        LogLevel::Info,
+        format!("Wrote {:?} ({} bytes)", target, summary.bytes_written),
+    );
+    Ok(target)
+}
+
+/// Extracts an archive into a scratch folder of the working dir and processes
+/// the extracted tree like a dropped folder named after the archive.
+fn process_archive(ctx: &JobContext, config: &Config) -> anyhow::Result<Vec<PathBuf>> {
+    let source = &ctx.job.source;
+    let scratch = ScratchDir::create(&config.working_dir, &format!("job-{}", ctx.job.id))?;
+    let limits = ArchiveLimits {
+        max_total_bytes: config.archive_max_bytes,
+        max_ratio: config.archive_max_ratio,
+        max_entries: config.archive_max_entries,
+        max_depth: config.archive_max_depth,
+        max_nesting: config.archive_max_nesting,
+    };
+    let extraction = archive::extract(source, scratch.path(), limits, ctx)
+        .with_context(|| format!("Failed to extract {:?}", source))?;
+    ctx.log(
+        LogLevel::Info,
+        format!(
+            "Extracted {} archive: {} file(s), {} bytes",
+            extraction.format, extraction.files, extraction.bytes
+        ),
+    );
+    if !extraction.skipped.is_empty() {
+        ctx.log(
+            LogLevel::Warn,
+            format!(
+                "Skipped {} archive entries: {}",
+                extraction.skipped.len(),
+                extraction.skipped.join(", ")
+            ),
+        );
+    }
+    for nested in &extraction.nested {
+        ctx.log(
+            LogLevel::Info,
+            format!(
+                "Unpacked nested archive {:?} ({}) into {:?}: {} file(s)",
+                nested.archive,
+                nested.chain.join(" > "),
+                nested.folder,
+                nested.files
+            ),
+        );
+    }
+    for packed in &extraction.left_packed {
+        ctx.log(
+            LogLevel::Warn,
+            format!("Left nested archive {:?} packed", packed),
+        );
+    }
+    ctx.check_cancelled()?;
+    let origin = |file: &Path| {
+        let relative = file.strip_prefix(scratch.path()).ok()?;
+        extraction
+            .origin(relative)
+            .map(|nested| nested.chain.join(" > "))
+    };
+    process_directory(
+        ctx,
+        config,
+        &content_root(scratch.path())?,
+        &extraction.format.stem(source),
+        &origin,
+    )
+}
+
+/// The single top-level folder most archives wrap their content in, or
+/// `dir` itself.
+fn content_root(dir: &Path) -> std::io::Result<PathBuf> {
+    let entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    match entries.as_slice() {
+        [only] if only.file_type()?.is_dir() => Ok(only.path()),
+        _ => Ok(dir.to_path_buf()),
+    }
+}
+
+/// Processes a folder (legacy `DirectoryProjectPlugin`): the folder is split
+/// into its models (see [`project::scan`]) and every model is built in a
+/// staging folder of the working dir, then moved to `<output>/<slug>`. The
+/// mesh files of each (variant) folder are merged into one 3MF there; 3MF
+/// packages, slicer projects, images and documents are copied, keeping their
+/// subfolders.
+/// `name` is the project name; `origin` names the nested archive(s) a file was
+/// unpacked from, if any.
+fn process_directory(
+    ctx: &JobContext,
+    config: &Config,
+    root: &Path,
+    name: &str,
+    origin: &dyn Fn(&Path) -> Option<String>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let project = project::scan(root, name)?;
+    let count = |kind| project.counts.get(&kind).copied().unwrap_or(0);
+    ctx.log(
+        LogLevel::Info,
+        format!(
+            "Folder: {} model file(s), {} slicer project(s), {} image(s), {} document(s), {} other file(s)",
+            count(FileKind::Model),
+            count(FileKind::SlicerProject),
+            count(FileKind::Image),
+            count(FileKind::Document),
+            count(FileKind::Other),
+        ),
+    );
+    if !project.junk.is_empty() {
+        let names: Vec<String> = project
+            .junk
+            .iter()
+            .map(|file| {
+                file.strip_prefix(root)
+                    .unwrap_or(file)
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        ctx.log(
+            LogLevel::Info,
+            format!("Dropped {} junk file(s): {}", names.len(), names.join(", ")),
+        );
+    }
+    if project.models.is_empty() {
+        anyhow::bail!("{:?} contains no models", name);
+    }
+    if project.is_collection() {
+        let names: Vec<&str> = project.models.iter().map(|m| m.name.as_str()).collect();
+        ctx.log(
+            LogLevel::Info,
+            format!("Collection of {} models: {}", names.len(), names.join(", ")),
+        );
+    }
+    for model in &project.models {
+        let sources = model
+            .of_kind(FileKind::Model)
+            .chain(model.of_kind(FileKind::SlicerProject));
+        for file in sources {
+            if let Some(origin) = origin(&file.path) {
+                ctx.log(
+                    LogLevel::Info,
+                    format!(
+                        "{:?} comes from {}",
+                        file.path.strip_prefix(root).unwrap_or(&file.path),
+                        origin
+                    ),
+                );
+            }
+        }
+    }
+
+    // Every model is staged before the first one is published, so a failing
+    // model leaves no partial collection in the library; a failing publish is
+    // rolled back by `publish_staged`.
+    let staging = create_staging(ctx, config)?;
+    let mut staged = Vec::new();
+    for model in &project.models {
+        let base = slugify(&model.name);
+        let mut slug = base.clone();
+        let mut n = 2;
+        while staged.iter().any(|(s, _)| *s == slug) {
+            slug = format!("{}-{}", base, n);
+            n += 1;
+        }
+        let model_dir = staging.path().join(&slug);
+        std::fs::create_dir_all(&model_dir)?;
+        let package = stage_model(ctx, config, root, model, &model_dir, &slug, origin)?;
+        write_datapackage(ctx, package, &model_dir, &config.output_dir.join(&slug))?;
+        staged.push((slug, model_dir));
+    }
+    publish_staged(ctx, config, &staged)
+}
+
+/// Builds one model's output folder in `model_dir`, keeping its folder
+/// layout: the meshes of each folder (variants such as `supported/` or
+/// `32mm/`) are merged into one 3MF in that folder, `<slug>.3mf` at the top
+/// and `<slug>-<folder>.3mf` below. The best-scoring image becomes the
+/// thumbnail of each 3MF. `root` is the scanned folder; `origin` names the
+/// nested archive(s) a file was unpacked from and is recorded in the
+/// datapackage.
+fn stage_model(
+    ctx: &JobContext,
+    config: &Config,
+    root: &Path,
+    model: &ModelGroup,
+    model_dir: &Path,
+    slug: &str,
+    origin: &dyn Fn(&Path) -> Option<String>,
+) -> anyhow::Result<Datapackage> {
+    let mut variants: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+    for file in model.of_kind(FileKind::Model) {
+        if matches!(
+            classify(&file.path),
+            InputKind::Stl | InputKind::Obj | InputKind::Ply | InputKind::Amf
+        ) {
+            let folder = file.relative.parent().unwrap_or(Path::new(""));
+            variants
+                .entry(folder.to_path_buf())
+                .or_default()
+                .push(file.path.clone());
+        }
+    }
+    // PNG, then JPEG; other formats cannot be a 3MF thumbnail.
+    let mut images: Vec<(u8, PathBuf)> = model
+        .of_kind(FileKind::Image)
+        .filter_map(|file| Some((image_rank(&file.path)?, file.path.clone())))
+        .collect();
+    images.sort();
+    let images: Vec<PathBuf> = images.into_iter().map(|(_, path)| path).collect();
+
+    let mut staged = Vec::new();
+    let mut previews = Vec::new();
+    for (folder, meshes) in &variants {
+        let (variant_slug, variant_name) = if folder.as_os_str().is_empty() {
+            (slug.to_string(), model.name.clone())
+        } else {
+            let folder_name = folder.to_string_lossy();
+            (
+                format!("{}-{}", slug, slugify(&folder_name)),
+                format!("{} ({})", model.name, folder_name),
+            )
+        };
+        let matches = match_previews(ctx, root, meshes, &images, true);
+        for found in &matches {
+            let relative = model
+                .files
+                .iter()
+                .find(|file| file.path == found.image)
+                .map(|file| file.relative.clone());
+            if let Some(relative) = relative.filter(|r| !previews.contains(r)) {
+                previews.push(relative);
+            }
+        }
+        let variant_dir = model_dir.join(folder);
+        std::fs::create_dir_all(&variant_dir)?;
+        staged.push(convert_meshes(
+            ctx,
+            config,
+            meshes,
+            &variant_dir,
+            &variant_slug,
+            &variant_name,
+            matches.first().map(|found| found.image.as_path()),
+        )?);
+    }
+    let meshes: Vec<&PathBuf> = variants.values().flatten().collect();
+    for file in &model.files {
+        let copy = match file.kind {
+            FileKind::Model => !meshes.contains(&&file.path),
+            FileKind::SlicerProject | FileKind::Image | FileKind::Document => true,
+            FileKind::Datapackage | FileKind::Junk | FileKind::Other => false,
+        };
+        if !copy {
+            continue;
+        }
+        ctx.check_cancelled()?;
+        let target = model_dir.join(&file.relative);
+        if staged.contains(&target) {
+            ctx.log(
+                LogLevel::Warn,
+                format!("Not copying {:?}: {:?} already exists", file.path, target),
+            );
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&file.path, &target)?;
+        staged.push(target);
+    }
+
+    let mut package =
+        datapackage::generate(model_dir, slug, &title_from_name(&model.name), &previews)?;
+    let packages = model
+        .files
+        .iter()
+        .filter(|file| matches!(file.kind, FileKind::Model | FileKind::SlicerProject))
+        .filter(|file| classify(&file.path) == InputKind::ThreeMf);
+    for file in packages {
+        match threemf::PackageReader::open(&file.path).and_then(|mut p| p.info()) {
+            Ok(info) => apply_package_info(&mut package, &info),
+            Err(e) => ctx.log(
+                LogLevel::Warn,
+                format!("No metadata from {:?}: {:#}", file.relative, e),
+            ),
+        }
+    }
+    for file in &model.files {
+        if let Some(chain) = origin(&file.path) {
+            let folder = file.relative.parent().unwrap_or(Path::new(""));
+            package.add_source(&chain, folder);
+        }
+    }
+
+    // A datapackage shipped with the model wins over generated values, like
+    // one already in the output folder; resources of files that were
+    // converted away are dropped.
+    let shipped = model
+        .of_kind(FileKind::Datapackage)
+        .find(|file| file.relative == Path::new(datapackage::FILE_NAME));
+    if let Some(file) = shipped {
+        match Datapackage::read(&file.path) {
+            Ok(mut shipped) => {
+                shipped
+                    .resources
+                    .retain(|resource| model_dir.join(&resource.path).is_file());
+                shipped.name = slug.to_string();
+                shipped.merge(package);
+                ctx.log(
+                    LogLevel::Info,
+                    format!("Merged the datapackage shipped in {:?}", file.relative),
+                );
+                package = shipped;
+            }
+            Err(e) => ctx.log(LogLevel::Warn, format!("{:#}; ignoring it", e)),
+        }
+    }
+    Ok(package)
+}
+
+/// Fills the datapackage description, license, creator and keywords from 3MF
+/// metadata.
+fn apply_package_info(package: &mut Datapackage, info: &PackageInfo) {
+    if package.description.is_empty() {
+        if let Some(description) = &info.metadata.description {
+            package.description = description.clone();
+        }
+    }
+    if package.licenses.is_empty() {
+        if let Some(license) = info.license() {
+            package.add_license(license);
+        }
+    }
+    if let Some(creator) = info.creator() {
+        if !package.contributors.iter().any(|c| c.title == creator) {
+            package.add_contributor(creator, "creator");
+        }
+    }
+    for tag in info.tags() {
+        if !package.keywords.contains(tag) {
+            package.keywords.push(tag.clone());
+        }
+    }
+}
+
+/// Writes `datapackage.json` into the staged `model_dir`, merged into the one
+/// already published in `published_dir`. An existing datapackage that cannot
+/// be read is left alone.
+fn write_datapackage(
+    ctx: &JobContext,
+    generated: Datapackage,
+    model_dir: &Path,
+    published_dir: &Path,
+) -> anyhow::Result<()> {
+    let existing = published_dir.join(datapackage::FILE_NAME);
+    let package = if existing.exists() {
+        match Datapackage::read(&existing) {
+            Ok(mut package) => {
+                let known = package.resources.len();
+                package.merge(generated);
+                ctx.log(
+                    LogLevel::Info,
+                    format!(
+                        "Merged into existing {:?}: {} new resource(s)",
+                        existing,
+                        package.resources.len() - known
+                    ),
+                );
+                package
+            }
+            Err(e) => {
+                ctx.log(LogLevel::Warn, format!("{:#}; leaving it unchanged", e));
+                return Ok(());
+            }
+        }
+    } else {
+        generated
+    };
+    package.write(&model_dir.join(datapackage::FILE_NAME))
+}
+
+/// Folder below the output volume that holds the models of running jobs.
+/// Hidden, so Manyfold does not list it as a model.
+const STAGING_DIR: &str = ".staging";
+
+/// Staging folder of the job's models on the output volume. Models are built
+/// there and only published once complete, so the library never shows a
+/// half-written model; being on the same file system as the library, every
+/// publish is a rename.
+fn create_staging(ctx: &JobContext, config: &Config) -> std::io::Result<ScratchDir> {
+    ScratchDir::create(
+        &config.output_dir.join(STAGING_DIR),
+        &format!("job-{}", ctx.job.id),
+    )
+}
+
+/// Publishes staged model folders `(slug, folder)` to `<output>/<slug>` and
+/// returns the published files. All or nothing: if one folder cannot be
+/// published, the ones before it are taken back out of the library.
+fn publish_staged(
+    ctx: &JobContext,
+    config: &Config,
+    staged: &[(String, PathBuf)],
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut undo = Vec::new();
+    let mut artifacts = Vec::new();
+    for (slug, model_dir) in staged {
+        let target = config.output_dir.join(slug);
+        let published = match publish(model_dir, &target, &mut undo) {
+            Ok(published) => published,
+            Err(e) => {
+                if !undo.is_empty() {
+                    ctx.log(LogLevel::Warn, "Publishing failed, rolling back");
+                    roll_back(ctx, undo);
+                }
+                return Err(e.context(format!("Cannot publish {:?}", target)));
+            }
+        };
+        ctx.log(
+            LogLevel::Info,
+            format!("Published {} file(s) to {:?}", published.len(), target),
+        );
+        artifacts.extend(published);
+    }
+    Ok(artifacts)
+}
+
+/// How to take back one step of [`publish`].
+enum Undo {
+    /// A folder or file that did not exist before.
+    Remove(PathBuf),
+    /// A file that was replaced; the original was moved to `backup`.
+    Restore { backup: PathBuf, dest: PathBuf },
+}
+
+/// Moves a staged model folder to `target` and returns the published files.
+/// Files already in `target` are replaced; other files there are kept. The
+/// replaced files are moved next to the staged folder (and go with the
+/// staging folder) so that `undo` can restore them.
+fn publish(staged: &Path, target: &Path, undo: &mut Vec<Undo>) -> anyhow::Result<Vec<PathBuf>> {
+    let files = project::walk_files(staged)?;
+    let published = files
+        .iter()
+        .map(|file| target.join(file.strip_prefix(staged).unwrap_or(file)))
+        .collect();
+    if !target.exists() {
+        std::fs::rename(staged, target)?;
+        undo.push(Undo::Remove(target.to_path_buf()));
+        return Ok(published);
+    }
+    let name = staged.file_name().unwrap_or_default();
+    let backups = staged.with_file_name(format!(".replaced-{}", name.to_string_lossy()));
+    for (file, dest) in files.iter().zip(&published) {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if dest.exists() {
+            let backup = backups.join(dest.strip_prefix(target).unwrap_or(dest));
+            if let Some(parent) = backup.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::rename(dest, &backup)?;
+            undo.push(Undo::Restore {
+                backup,
+                dest: dest.clone(),
+            });
+        } else {
+            undo.push(Undo::Remove(dest.clone()));
+        }
+        std::fs::rename(file, dest)?;
+    }
+    Ok(published)
+}
+
+/// Reverts the steps of [`publish`], newest first.
+fn roll_back(ctx: &JobContext, undo: Vec<Undo>) {
+    for step in undo.into_iter().rev() {
+        let (result, path) = match step {
+            Undo::Remove(path) if path.is_dir() => (std::fs::remove_dir_all(&path), path),
+            Undo::Remove(path) => match std::fs::remove_file(&path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (Ok(()), path),
+                result => (result, path),
+            },
+            Undo::Restore { backup, dest } => (std::fs::rename(&backup, &dest), dest),
+        };
+        if let Err(e) = result {
+            ctx.log(
+                LogLevel::Error,
+                format!("Cannot roll back {:?}: {}", path, e),
+            );
+        }
+    }
+}
+
+/// Preference of an image as preview: PNG before JPEG; `None` for other files.
+fn image_rank(path: &Path) -> Option<u8> {
+    let ext = path.extension()?.to_string_lossy().to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some(0),
+        "jpg" | "jpeg" => Some(1),
+        _ => None,
+    }
+}
+
+/// Per-job scratch or staging folder, removed again on drop.
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    /// Creates `<parent>/<name>`, clearing leftovers of an earlier attempt
+    /// that crashed.
+    fn create(parent: &Path, name: &str) -> std::io::Result<Self> {
+        let path = parent.join(name);
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        std::fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            log::warn!("Pipeline: cannot remove scratch dir {:?}: {}", self.0, e);
+        }
+    }
+}
+
+/// PNG/JPEG files next to `source`, PNG first.
+fn sibling_images(source: &Path) -> Vec<PathBuf> {
+    let Some(Ok(entries)) = source.parent().map(std::fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut images: Vec<(u8, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| Some((image_rank(&path)?, path)))
+        .collect();
+    images.sort();
+    images.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Scores `images` against `models` (see [`preview::match_previews`]) and logs
+/// every match with its score.
+fn match_previews(
+    ctx: &JobContext,
+    root: &Path,
+    models: &[PathBuf],
+    images: &[PathBuf],
+    single_image_rule: bool,
+) -> Vec<PreviewMatch> {
+    let matches = preview::match_previews(models, images, single_image_rule);
+    for found in &matches {
+        ctx.log(
+            LogLevel::Info,
+            format!(
+                "Preview for {:?}: {:?}, score {}",
+                found.model.strip_prefix(root).unwrap_or(&found.model),
+                found.image.strip_prefix(root).unwrap_or(&found.image),
+                found.score
+            ),
+        );
+    }
+    matches
+}
+
+/// Files a ready-made 3MF as `<output>/<slug>/<slug>.3mf`, next to its
+/// embedded preview image, staged like a folder (see [`process_directory`]).
+fn process_threemf(ctx: &JobContext, config: &Config) -> anyhow::Result<Vec<PathBuf>> {
+    let source = &ctx.job.source;
+    ctx.progress(Stage::Parsing, 0.0, None);
+    let mut package = threemf::PackageReader::open(source)
+        .with_context(|| format!("Failed to read {:?}", source))?;
+    let info = package
+        .info()
+        .with_context(|| format!("Failed to read {:?}", source))?;
+    ctx.progress(Stage::Parsing, 1.0, None);
+    ctx.log(
+        LogLevel::Info,
+        format!(
+            "3MF: {} object(s), {} build item(s), {} image(s), title {:?}, creator {:?}, license {:?}",
+            info.object_count,
+            info.build_item_count,
+            info.thumbnails.len(),
+            info.title().unwrap_or("-"),
+            info.creator().unwrap_or("-"),
+            info.license().unwrap_or("-"),
+        ),
+    );
+    if let Some(bambu) = &info.bambu {
+        ctx.log(
+            LogLevel::Info,
+            format!(
+                "Bambu project ({}): {} plate(s), tags [{}]",
+                bambu
+                    .application
+                    .as_deref()
+                    .unwrap_or("unknown application"),
+                bambu.plates.len(),
+                info.tags().join(", ")
+            ),
+        );
+    }
+    if !info.required_extensions.is_empty() {
+        ctx.log(
+            LogLevel::Info,
+            format!(
+                "3MF requires extensions: {}",
+                info.required_extensions.join(" ")
+            ),
+        );
+    }
+    ctx.check_cancelled()?;
+
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let slug = slugify(&stem);
+    let staging = create_staging(ctx, config)?;
+    let model_dir = staging.path().join(&slug);
+    std::fs::create_dir_all(&model_dir)?;
+
+    std::fs::copy(source, model_dir.join(format!("{}.3mf", slug)))?;
+    ctx.progress(Stage::Zipping, 1.0, None);
+    let mut previews = Vec::new();
+
+    if let Some(preview) = info.preview() {
+        let ext = Path::new(&preview.part)
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_else(|| "png".to_string());
+        let image = PathBuf::from(format!("{}.{}", slug, ext));
+        match package.extract_image(&preview.part, &model_dir.join(&image))? {
+            Ok(_) => {
+                ctx.log(
+                    LogLevel::Info,
+                    format!("Extracted preview {} as {:?}", preview.part, image),
+                );
+                previews.push(image);
+            }
+            Err(reason) => ctx.log(
+                LogLevel::Warn,
+                format!("Skipped preview {}: {}", preview.part, reason),
+            ),
+        }
+    }
+
+    // Bambu plate renders; the one already used as the preview is not repeated.
+    let plates = info.bambu.as_ref().map_or(&[][..], |b| b.plates.as_slice());
+    for plate in plates {
+        let Some(thumbnail) = &plate.thumbnail else {
+            continue;
+        };
+        if info.preview().is_some_and(|p| &p.part == thumbnail) {
+            continue;
+        }
+        ctx.check_cancelled()?;
+        let image = PathBuf::from(format!("{}-plate-{}.png", slug, plate.index));
+        match package.extract_image(thumbnail, &model_dir.join(&image))? {
+            Ok(_) => previews.push(image),
+            Err(reason) => ctx.log(
+                LogLevel::Warn,
+                format!("Skipped plate render {}: {}", thumbnail, reason),
+            ),
+        }
+    }
+
+    let title = info
+        .title()
+        .map_or_else(|| title_from_name(&stem), str::to_string);
+    let mut package = datapackage::generate(&model_dir, &slug, &title, &previews)?;
+    apply_package_info(&mut package, &info);
+    write_datapackage(ctx, package, &model_dir, &config.output_dir.join(&slug))?;
+    publish_staged(ctx, config, &[(slug, model_dir)])
+}
+
+/// Machine-readable folder/file name: lowercase ASCII alphanumerics separated by `-`.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    if slug.is_empty() {
+        slug.push_str("model");
+    }
+    slug
+}
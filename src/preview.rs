@@ -0,0 +1,195 @@
+//! Preview Matching
+//!
+//! Governance: .agent/skills/architectural_guidelines/SKILL.md (Level 3: Processing Pipeline)
+//!
+//! Re-implements the legacy "Token Overlap" sibling search of
+//! `StlProjectPlugin` (`car_v1.stl` <-> `car_preview.jpg`) as a score, so the
+//! best image wins and the job log can show why it was picked:
+//!
+//! *   **Name**: same file stem, one stem containing the other, and shared
+//!     filename tokens of three characters or more. The legacy plugin
+//!     wanted four, which missed its own `car` example.
+//! *   **Proximity**: images in the model's folder beat images in a parent
+//!     or a `renders/` subfolder.
+//! *   **Suffix**: `_render`, `_preview`, `_thumb`, ... mark an image made to
+//!     show the model.
+//!
+//! Only images with some name evidence match. Inside a dropped folder a
+//! lone image is used anyway when nothing matched by name, as in the legacy
+//! plugin ("Single Image Rule"); loose files in the input folder share it
+//! with unrelated drops, so they never get that fallback.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+const SAME_STEM: u32 = 40;
+const CONTAINED_STEM: u32 = 20;
+const PER_TOKEN: u32 = 10;
+const SAME_FOLDER: u32 = 15;
+/// Proximity points lost per folder step between model and image.
+const PER_STEP: u32 = 5;
+const PREVIEW_SUFFIX: u32 = 5;
+
+/// Filename tokens that mark an image as a preview of a model.
+const PREVIEW_SUFFIXES: &[&str] = &[
+    "render",
+    "renders",
+    "preview",
+    "thumb",
+    "thumbnail",
+    "cover",
+    "photo",
+    "painted",
+];
+
+/// Lowercase filename tokens split at `-`, `_`, `.` and whitespace, like the
+/// legacy `tokenize`.
+pub fn tokenize(name: &str) -> BTreeSet<String> {
+    name.to_lowercase()
+        .split(|c: char| c == '-' || c == '_' || c == '.' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Points of one image for one model, with the reasons that earned them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Score {
+    pub points: u32,
+    /// Whether the filenames are related at all.
+    pub named: bool,
+    pub reasons: Vec<String>,
+}
+
+impl Score {
+    fn add(&mut self, points: u32, reason: String) {
+        if points > 0 {
+            self.points += points;
+            self.reasons.push(format!("{} +{}", reason, points));
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.points, self.reasons.join(", "))
+    }
+}
+
+/// Scores `image` as a preview of `model`.
+pub fn score(model: &Path, image: &Path) -> Score {
+    let model_stem = stem(model);
+    let image_stem = stem(image);
+    let mut score = Score::default();
+
+    if model_stem == image_stem {
+        score.add(SAME_STEM, "same name".to_string());
+    } else if contains_stem(&model_stem, &image_stem) || contains_stem(&image_stem, &model_stem) {
+        score.add(CONTAINED_STEM, "name contained".to_string());
+    }
+    let model_tokens = tokenize(&model_stem);
+    let image_tokens = tokenize(&image_stem);
+    let shared: Vec<&str> = model_tokens
+        .intersection(&image_tokens)
+        .filter(|token| token.len() >= 3 && !PREVIEW_SUFFIXES.contains(&token.as_str()))
+        .map(String::as_str)
+        .collect();
+    score.add(
+        PER_TOKEN * shared.len() as u32,
+        format!("tokens [{}]", shared.join(" ")),
+    );
+    score.named = score.points > 0;
+
+    let steps = folder_steps(model.parent(), image.parent());
+    let proximity = SAME_FOLDER.saturating_sub(PER_STEP * steps);
+    if steps == 0 {
+        score.add(proximity, "same folder".to_string());
+    } else {
+        score.add(proximity, format!("{} folder step(s) away", steps));
+    }
+
+    if let Some(suffix) = image_tokens
+        .difference(&model_tokens)
+        .find(|token| PREVIEW_SUFFIXES.contains(&token.as_str()))
+    {
+        score.add(PREVIEW_SUFFIX, format!("_{} suffix", suffix));
+    }
+    score
+}
+
+/// An image picked as the preview of a model file.
+#[derive(Debug, Clone)]
+pub struct PreviewMatch {
+    pub model: PathBuf,
+    pub image: PathBuf,
+    pub score: Score,
+}
+
+/// The best image for every model that has one, best match first. Ties go to
+/// the image listed first in `images`, so callers list preferred formats
+/// first. `single_image_rule` lets a lone unrelated image match every model.
+pub fn match_previews(
+    models: &[PathBuf],
+    images: &[PathBuf],
+    single_image_rule: bool,
+) -> Vec<PreviewMatch> {
+    let mut matches: Vec<PreviewMatch> = models
+        .iter()
+        .filter_map(|model| {
+            images
+                .iter()
+                .map(|image| (image, score(model, image)))
+                .filter(|(_, score)| score.named)
+                .fold(
+                    None,
+                    |best: Option<(&PathBuf, Score)>, candidate| match best {
+                        Some(best) if best.1.points >= candidate.1.points => Some(best),
+                        _ => Some(candidate),
+                    },
+                )
+                .map(|(image, score)| PreviewMatch {
+                    model: model.clone(),
+                    image: image.clone(),
+                    score,
+                })
+        })
+        .collect();
+
+    if let ([image], true) = (images, single_image_rule && matches.is_empty()) {
+        matches = models
+            .iter()
+            .map(|model| {
+                let mut score = score(model, image);
+                score.reasons.insert(0, "only image".to_string());
+                PreviewMatch {
+                    model: model.clone(),
+                    image: image.clone(),
+                    score,
+                }
+            })
+            .collect();
+    }
+    matches.sort_by_key(|found| std::cmp::Reverse(found.score.points));
+    matches
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Legacy containment test; stems shorter than three characters would match
+/// too much.
+fn contains_stem(outer: &str, inner: &str) -> bool {
+    inner.len() >= 3 && outer.contains(inner)
+}
+
+/// Folder steps from `from` to `to` via their common ancestor.
+fn folder_steps(from: Option<&Path>, to: Option<&Path>) -> u32 {
+    let from: Vec<Component> = from.map_or_else(Vec::new, |p| p.components().collect());
+    let to: Vec<Component> = to.map_or_else(Vec::new, |p| p.components().collect());
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    (from.len() - common + to.len() - common) as u32
+}
@@ -0,0 +1,101 @@
+//! Live Job Progress Events
+//!
+//! Governance: .agent/skills/observability_standards/SKILL.md
+//!
+//! Replaces the legacy `PROGRESS: Parsing STL 1/3: 45%` stdout lines with typed
+//! events on a broadcast bus. The web server streams them to the dashboard as
+//! Server-Sent Events; the latest progress of each running job is retained so
+//! a client connecting mid-job immediately sees where it is.
+
+use crate::queue::JobState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before a slow client starts missing some.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Processing stage reported alongside a percentage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Routing,
+    Extracting,
+    Parsing,
+    Deduplicating,
+    Zipping,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobEvent {
+    /// Stage/percentage update from a running job.
+    Progress {
+        job_id: u64,
+        stage: Stage,
+        percent: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+    /// Lifecycle transition (including deletion, reported as `None`).
+    State {
+        job_id: u64,
+        state: Option<JobState>,
+    },
+}
+
+impl JobEvent {
+    pub fn job_id(&self) -> u64 {
+        match self {
+            JobEvent::Progress { job_id, .. } | JobEvent::State { job_id, .. } => *job_id,
+        }
+    }
+}
+
+/// Cloneable handle to the process-wide event bus.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<JobEvent>,
+    latest: Arc<Mutex<HashMap<u64, JobEvent>>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            tx,
+            latest: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn publish(&self, event: JobEvent) {
+        {
+            let mut latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+            match &event {
+                JobEvent::Progress { job_id, .. } => {
+                    latest.insert(*job_id, event.clone());
+                }
+                JobEvent::State { job_id, .. } => {
+                    latest.remove(job_id);
+                }
+            }
+        }
+        // No subscribers is not an error: nobody has the dashboard open.
+        let _ = self.tx.send(event);
+    }
+
+    /// Returns the retained progress of running jobs and a receiver for new events.
+    pub fn subscribe(&self) -> (Vec<JobEvent>, broadcast::Receiver<JobEvent>) {
+        let latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+        // Subscribe while holding the lock so no event falls between snapshot and stream.
+        let rx = self.tx.subscribe();
+        (latest.values().cloned().collect(), rx)
+    }
+}
@@ -0,0 +1,308 @@
+//! Directory Projects
+//!
+//! Governance: .agent/skills/architectural_guidelines/SKILL.md (Level 3: Processing Pipeline)
+//!
+//! Replaces the legacy `DirectoryProjectPlugin`. A dropped (or extracted)
+//! folder is scanned, every file is classified, and the folder is split into
+//! the models it holds:
+//!
+//! *   **One model**: model files at the top level, a single subfolder with
+//!     models, or subfolders that look like variants of one model
+//!     (`supported/`, `unsupported/`, `32mm/`, `stl/`, ...).
+//! *   **A collection**: two or more subfolders with models and no models at
+//!     the top level. Each such subfolder becomes its own model; top-level
+//!     files and subfolders without models (documents, renders) are shared
+//!     by all of them.
+//!
+//! Junk (`.DS_Store`, `Thumbs.db`, `__MACOSX/`, AppleDouble `._*` files) is
+//! dropped. A `datapackage.json` (e.g. from a Manyfold export) belongs to the
+//! folder it is in; the one at the top of a collection describes the
+//! collection, not its models, and is not shared.
+
+use crate::threemf::PackageReader;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What a file in a project folder is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FileKind {
+    /// Mesh files (STL, OBJ, PLY, AMF) and plain 3MF packages.
+    Model,
+    /// Slicer project files and sliced output (3MF with slicer settings,
+    /// Lychee/Chitubox scenes, G-code).
+    SlicerProject,
+    Image,
+    Document,
+    /// A Manyfold `datapackage.json` shipped with the model.
+    Datapackage,
+    /// OS metadata files that are never kept.
+    Junk,
+    Other,
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FileKind::Model => "model",
+            FileKind::SlicerProject => "slicer project",
+            FileKind::Image => "image",
+            FileKind::Document => "document",
+            FileKind::Datapackage => "datapackage",
+            FileKind::Junk => "junk",
+            FileKind::Other => "other",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Tokens of folder names that denote a variant of one model rather than a
+/// separate model.
+const VARIANT_TOKENS: &[&str] = &[
+    "supported",
+    "unsupported",
+    "presupported",
+    "pre",
+    "support",
+    "supports",
+    "nosupports",
+    "stl",
+    "stls",
+    "obj",
+    "3mf",
+    "files",
+    "parts",
+    "lys",
+    "lychee",
+    "chitubox",
+    "fdm",
+    "resin",
+    "hollow",
+    "hollowed",
+    "solid",
+    "scaled",
+    "variant",
+    "variants",
+];
+
+/// Extensions of [`FileKind::Image`] files.
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+
+/// Classifies one file by name; 3MF packages are opened to tell slicer
+/// projects from plain models.
+pub fn classify_file(path: &Path) -> FileKind {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if is_junk(path) {
+        return FileKind::Junk;
+    }
+    if name == crate::datapackage::FILE_NAME {
+        return FileKind::Datapackage;
+    }
+    let ext = name.rsplit_once('.').map_or("", |(_, ext)| ext);
+    match ext {
+        "stl" | "obj" | "ply" | "amf" => FileKind::Model,
+        "3mf" if is_slicer_project(path) => FileKind::SlicerProject,
+        "3mf" => FileKind::Model,
+        "gcode" | "bgcode" | "gco" | "lys" | "lyt" | "chitubox" | "ctb" | "factory" => {
+            FileKind::SlicerProject
+        }
+        ext if IMAGE_EXTENSIONS.contains(&ext) => FileKind::Image,
+        "pdf" | "txt" | "md" | "rtf" | "doc" | "docx" | "odt" | "html" | "htm" => {
+            FileKind::Document
+        }
+        _ => FileKind::Other,
+    }
+}
+
+fn is_junk(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    matches!(name.as_str(), ".ds_store" | "thumbs.db" | "desktop.ini")
+        || name.starts_with("._")
+        || path.components().any(|c| c.as_os_str() == "__MACOSX")
+}
+
+/// Bambu Studio / OrcaSlicer and PrusaSlicer store their settings in the
+/// package; Cura keeps a `Cura/` folder.
+fn is_slicer_project(path: &Path) -> bool {
+    let Ok(package) = PackageReader::open(path) else {
+        return false;
+    };
+    package.has_part("Metadata/project_settings.config")
+        || package.has_part("Metadata/Slic3r_PE.config")
+        || package.part_names().any(|part| part.starts_with("Cura/"))
+}
+
+/// A classified file of a project folder.
+#[derive(Debug, Clone)]
+pub struct ProjectFile {
+    pub path: PathBuf,
+    /// Path inside the model's output folder.
+    pub relative: PathBuf,
+    pub kind: FileKind,
+}
+
+impl ProjectFile {
+    /// Model files and 3MF slicer projects make a folder a model; G-code or
+    /// images alone do not.
+    fn defines_model(&self) -> bool {
+        match self.kind {
+            FileKind::Model => true,
+            FileKind::SlicerProject => self
+                .path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("3mf")),
+            _ => false,
+        }
+    }
+}
+
+/// One model found in a project folder.
+#[derive(Debug, Clone)]
+pub struct ModelGroup {
+    /// Display name the output slug is derived from.
+    pub name: String,
+    pub files: Vec<ProjectFile>,
+}
+
+impl ModelGroup {
+    pub fn of_kind(&self, kind: FileKind) -> impl Iterator<Item = &ProjectFile> {
+        self.files.iter().filter(move |file| file.kind == kind)
+    }
+}
+
+/// Result of [`scan`].
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// Empty if the folder holds no models.
+    pub models: Vec<ModelGroup>,
+    /// Files of every kind found, including junk.
+    pub counts: BTreeMap<FileKind, usize>,
+    pub junk: Vec<PathBuf>,
+}
+
+impl Project {
+    pub fn is_collection(&self) -> bool {
+        self.models.len() > 1
+    }
+}
+
+/// Scans `root` (the project `name`) and groups its files into models.
+pub fn scan(root: &Path, name: &str) -> std::io::Result<Project> {
+    let mut counts = BTreeMap::new();
+    let mut junk = Vec::new();
+    let mut files = Vec::new();
+    for path in walk_files(root)? {
+        let kind = classify_file(&path);
+        *counts.entry(kind).or_insert(0) += 1;
+        if kind == FileKind::Junk {
+            junk.push(path);
+            continue;
+        }
+        let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+        files.push(ProjectFile {
+            path,
+            relative,
+            kind,
+        });
+    }
+
+    // Top-level subfolder of each file; `None` for files directly in `root`.
+    let top_folder = |file: &ProjectFile| -> Option<String> {
+        let mut components = file.relative.components();
+        let first = components.next()?;
+        components
+            .next()
+            .map(|_| first.as_os_str().to_string_lossy().into_owned())
+    };
+    let mut model_folders: Vec<String> = files
+        .iter()
+        .filter(|file| file.defines_model())
+        .filter_map(top_folder)
+        .collect();
+    model_folders.dedup();
+    let root_models = files
+        .iter()
+        .any(|file| file.defines_model() && top_folder(file).is_none());
+
+    let models = if !files.iter().any(ProjectFile::defines_model) {
+        Vec::new()
+    } else if root_models
+        || model_folders.len() < 2
+        || model_folders.iter().any(|folder| is_variant_name(folder))
+    {
+        vec![ModelGroup {
+            name: name.to_string(),
+            files,
+        }]
+    } else {
+        let (own, mut shared): (Vec<_>, Vec<_>) = files
+            .into_iter()
+            .partition(|file| top_folder(file).is_some_and(|f| model_folders.contains(&f)));
+        shared.retain(|file| file.kind != FileKind::Datapackage);
+        model_folders
+            .iter()
+            .map(|folder| ModelGroup {
+                name: folder.clone(),
+                files: own
+                    .iter()
+                    .filter(|file| top_folder(file).as_ref() == Some(folder))
+                    .map(|file| ProjectFile {
+                        relative: file
+                            .relative
+                            .strip_prefix(folder)
+                            .unwrap_or(&file.relative)
+                            .to_path_buf(),
+                        ..file.clone()
+                    })
+                    .chain(shared.iter().cloned())
+                    .collect(),
+            })
+            .collect()
+    };
+
+    Ok(Project {
+        models,
+        counts,
+        junk,
+    })
+}
+
+/// `supported`, `32mm`, `75%`, `STL files`, ...
+fn is_variant_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    lower
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '%')
+        .filter(|token| !token.is_empty())
+        .any(|token| {
+            VARIANT_TOKENS.contains(&token)
+                || token
+                    .strip_suffix("mm")
+                    .or_else(|| token.strip_suffix('%'))
+                    .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        })
+}
+
+/// Every regular file below `root`, sorted by path. Symlinks are not followed.
+pub fn walk_files(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
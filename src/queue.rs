@@ -0,0 +1,658 @@
+//! Persistent Job Queue
+//!
+//! Governance: .agent/skills/architectural_guidelines/SKILL.md (Level 3: Processing Pipeline)
+//!
+//! Jobs are stored as compact JSON under the `/config` volume and rewritten
+//! atomically (temp file + rename) on every state change. Log lines alone are
+//! written at most every [`LOG_PERSIST_INTERVAL`]; the next state change
+//! flushes them. Only the newest `retention` finished jobs are kept; the
+//! sources of pruned ones are remembered so rescans do not redo them. On
+//! startup, jobs that were `running` when the process died (restart, OOM
+//! kill) are re-queued, or failed once they have used up their attempts so a
+//! poison input cannot crash-loop the container.
+
+use crate::progress::{EventBus, JobEvent};
+use crate::watcher;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// File name of the queue state inside the config directory.
+pub const QUEUE_FILE: &str = "jobs.json";
+
+/// Per-job log lines beyond this are dropped (oldest first) to bound the queue file.
+const MAX_LOG_ENTRIES: usize = 200;
+/// Least time between queue file writes caused by log lines alone.
+const LOG_PERSIST_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    /// Finished jobs can be deleted or retried; active ones cannot.
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobState::Succeeded | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JobState::Pending => "pending",
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub at: u64,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    /// Top-level item in the input directory (file or project folder).
+    pub source: PathBuf,
+    pub state: JobState,
+    /// Number of times the job has been started.
+    pub attempts: u32,
+    /// Unix timestamps (seconds).
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// Modification time of the source when it was enqueued, used to skip
+    /// re-enqueueing unchanged inputs on rescans.
+    #[serde(default)]
+    pub source_modified: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Machine-readable cause of the last failure (e.g. `stl_truncated`), so
+    /// clients can tell rejected inputs from transient errors.
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+    /// Set by the API; the worker stops at the next checkpoint.
+    #[serde(default)]
+    pub cancel_requested: bool,
+    #[serde(default)]
+    pub log: Vec<LogEntry>,
+    /// Files produced by the job (output folders, 3MFs, sidecars).
+    #[serde(default)]
+    pub artifacts: Vec<PathBuf>,
+}
+
+/// Error reported by a job handler.
+#[derive(Debug, Clone)]
+pub struct JobFailure {
+    pub message: String,
+    /// Stable code identifying why the input was rejected, if known.
+    pub reason: Option<&'static str>,
+}
+
+/// Why a job management request was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueError {
+    NotFound(u64),
+    InvalidState { id: u64, state: JobState },
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::NotFound(id) => write!(f, "Job {} not found", id),
+            QueueError::InvalidState { id, state } => {
+                write!(f, "Job {} is {} and cannot be changed that way", id, state)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueueError {}
+
+/// Aggregate counters for the status endpoint.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct QueueCounts {
+    pub pending: usize,
+    pub running: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueFile {
+    next_id: u64,
+    jobs: Vec<Job>,
+    /// Source modification times of pruned finished jobs whose source is
+    /// still in the input folder, so rescans do not process them again.
+    #[serde(default)]
+    pruned: BTreeMap<PathBuf, Option<u64>>,
+}
+
+/// Durable FIFO of processing jobs shared between the watcher, the worker and the web API.
+pub struct JobQueue {
+    path: PathBuf,
+    /// Finished jobs kept; older ones are pruned.
+    retention: usize,
+    events: EventBus,
+    inner: Mutex<QueueFile>,
+    /// Number of the latest snapshot of `inner`; only advanced while it is held.
+    generation: AtomicU64,
+    /// Number of the snapshot on disk; held while the file is written.
+    written: Mutex<u64>,
+    /// Last queue file write.
+    persisted_at: Mutex<Instant>,
+    wakeup: Notify,
+}
+
+/// The queue file serialized under the lock, written after it is released.
+struct Snapshot {
+    generation: u64,
+    json: serde_json::Result<Vec<u8>>,
+}
+
+impl JobQueue {
+    /// Loads (or creates) the queue file in `config_dir` and performs crash
+    /// recovery. `retention` finished jobs are kept.
+    pub fn open(
+        config_dir: &Path,
+        max_attempts: u32,
+        retention: usize,
+        events: EventBus,
+    ) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(config_dir)?;
+        let path = config_dir.join(QUEUE_FILE);
+
+        let mut file: QueueFile = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| anyhow::anyhow!("Queue file {:?} is corrupt: {}", path, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => QueueFile::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let now = now_secs();
+        for job in file
+            .jobs
+            .iter_mut()
+            .filter(|j| j.state == JobState::Running)
+        {
+            job.updated_at = now;
+            if job.cancel_requested {
+                job.state = JobState::Cancelled;
+                job.cancel_requested = false;
+            } else if job.attempts >= max_attempts {
+                log::warn!(
+                    "Queue: job {} ({:?}) was interrupted on its last attempt, marking failed",
+                    job.id,
+                    job.source
+                );
+                job.state = JobState::Failed;
+                job.error = Some(format!(
+                    "Interrupted by restart after {} attempt(s)",
+                    job.attempts
+                ));
+                job.failure_reason = Some("interrupted".to_string());
+            } else {
+                log::warn!(
+                    "Queue: job {} ({:?}) was interrupted, re-queueing",
+                    job.id,
+                    job.source
+                );
+                job.state = JobState::Pending;
+            }
+        }
+        file.next_id = file
+            .next_id
+            .max(file.jobs.iter().map(|j| j.id + 1).max().unwrap_or(1));
+
+        let queue = Self {
+            path,
+            retention,
+            events,
+            inner: Mutex::new(file),
+            generation: AtomicU64::new(0),
+            written: Mutex::new(0),
+            persisted_at: Mutex::new(Instant::now()),
+            wakeup: Notify::new(),
+        };
+        let pruned = {
+            let mut file = queue.lock();
+            let pruned = prune(&mut file, retention);
+            let snapshot = queue.snapshot(&file);
+            drop(file);
+            queue.persist(snapshot);
+            pruned
+        };
+        if !pruned.is_empty() {
+            log::info!("Queue: pruned {} old finished job(s)", pruned.len());
+        }
+        log::info!("Queue: loaded {:?} ({:?})", queue.path, queue.counts());
+        Ok(queue)
+    }
+
+    /// Adds a pending job for `source` and returns its id.
+    ///
+    /// Returns `None` when an equivalent job already exists: one that is pending or
+    /// running, or a finished one for the same source modification time. Finished
+    /// jobs for unchanged inputs are re-run explicitly, not by rescans.
+    pub fn enqueue(&self, source: PathBuf) -> Option<u64> {
+        let source_modified = modified_secs(&source);
+        let mut file = self.lock();
+        let duplicate = file.jobs.iter().any(|job| {
+            job.source == source
+                && match job.state {
+                    JobState::Pending | JobState::Running => true,
+                    JobState::Succeeded | JobState::Failed | JobState::Cancelled => {
+                        job.source_modified == source_modified
+                    }
+                }
+        }) || file.pruned.get(&source) == Some(&source_modified);
+        if duplicate {
+            log::debug!("Queue: {:?} already queued or processed, skipping", source);
+            return None;
+        }
+        file.pruned.remove(&source);
+
+        let now = now_secs();
+        let id = file.next_id;
+        file.next_id += 1;
+        log::info!("Queue: job {} enqueued for {:?}", id, source);
+        file.jobs.push(Job {
+            id,
+            source,
+            state: JobState::Pending,
+            attempts: 0,
+            created_at: now,
+            updated_at: now,
+            source_modified,
+            error: None,
+            failure_reason: None,
+            cancel_requested: false,
+            log: Vec::new(),
+            artifacts: Vec::new(),
+        });
+        let snapshot = self.snapshot(&file);
+        drop(file);
+        self.persist(snapshot);
+        self.publish_state(id, Some(JobState::Pending));
+        self.wakeup.notify_one();
+        Some(id)
+    }
+
+    /// Waits for the oldest pending job, marks it running and returns a copy.
+    pub async fn next(&self) -> Job {
+        loop {
+            // Register interest before checking so a concurrent enqueue is not missed.
+            let notified = self.wakeup.notified();
+            if let Some(job) = self.claim_next() {
+                return job;
+            }
+            notified.await;
+        }
+    }
+
+    fn claim_next(&self) -> Option<Job> {
+        let mut file = self.lock();
+        let job = file
+            .jobs
+            .iter_mut()
+            .filter(|j| j.state == JobState::Pending)
+            .min_by_key(|j| j.id)?;
+        job.state = JobState::Running;
+        job.attempts += 1;
+        job.updated_at = now_secs();
+        job.error = None;
+        job.failure_reason = None;
+        job.artifacts.clear();
+        push_log(
+            job,
+            LogLevel::Info,
+            format!("Attempt {} started", job.attempts),
+        );
+        let claimed = job.clone();
+        let snapshot = self.snapshot(&file);
+        drop(file);
+        self.persist(snapshot);
+        self.publish_state(claimed.id, Some(JobState::Running));
+        Some(claimed)
+    }
+
+    /// Records the outcome of a running job and the artifacts it produced.
+    ///
+    /// A failure after a cancel request is recorded as `cancelled`.
+    pub fn finish(&self, id: u64, result: Result<Vec<PathBuf>, JobFailure>) {
+        let mut file = self.lock();
+        let Some(job) = file.jobs.iter_mut().find(|j| j.id == id) else {
+            log::warn!("Queue: finished job {} no longer exists", id);
+            return;
+        };
+        job.updated_at = now_secs();
+        match result {
+            Ok(artifacts) => {
+                log::info!("Queue: job {} succeeded", id);
+                job.state = JobState::Succeeded;
+                job.error = None;
+                push_log(
+                    job,
+                    LogLevel::Info,
+                    format!("Succeeded with {} artifact(s)", artifacts.len()),
+                );
+                job.artifacts = artifacts;
+            }
+            Err(_) if job.cancel_requested => {
+                log::info!("Queue: job {} cancelled", id);
+                job.state = JobState::Cancelled;
+                push_log(job, LogLevel::Warn, "Cancelled".to_string());
+            }
+            Err(failure) => {
+                log::error!("Queue: job {} failed: {}", id, failure.message);
+                job.state = JobState::Failed;
+                push_log(job, LogLevel::Error, failure.message.clone());
+                job.error = Some(failure.message);
+                job.failure_reason = failure.reason.map(str::to_string);
+            }
+        }
+        job.cancel_requested = false;
+        let state = job.state;
+        let pruned = prune(&mut file, self.retention);
+        let snapshot = self.snapshot(&file);
+        drop(file);
+        self.persist(snapshot);
+        self.publish_state(id, Some(state));
+        for id in pruned {
+            self.publish_state(id, None);
+        }
+    }
+
+    /// Appends a line to the job's log. The queue file is rewritten at most
+    /// every [`LOG_PERSIST_INTERVAL`] for log lines; state changes write it
+    /// anyway.
+    pub fn log(&self, id: u64, level: LogLevel, message: impl Into<String>) {
+        let mut file = self.lock();
+        let Some(job) = file.jobs.iter_mut().find(|j| j.id == id) else {
+            return;
+        };
+        push_log(job, level, message.into());
+        let due = self
+            .persisted_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .elapsed()
+            >= LOG_PERSIST_INTERVAL;
+        if due {
+            let snapshot = self.snapshot(&file);
+            drop(file);
+            self.persist(snapshot);
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.lock().jobs.iter().find(|j| j.id == id).cloned()
+    }
+
+    /// All jobs, newest first, optionally filtered by state.
+    pub fn list(&self, state: Option<JobState>) -> Vec<Job> {
+        let file = self.lock();
+        file.jobs
+            .iter()
+            .rev()
+            .filter(|j| state.is_none_or(|s| j.state == s))
+            .cloned()
+            .collect()
+    }
+
+    /// Puts a failed or cancelled job back into the queue with a fresh attempt budget.
+    pub fn retry(&self, id: u64) -> Result<Job, QueueError> {
+        let mut file = self.lock();
+        let job = find_mut(&mut file, id)?;
+        if !matches!(job.state, JobState::Failed | JobState::Cancelled) {
+            return Err(QueueError::InvalidState {
+                id,
+                state: job.state,
+            });
+        }
+        job.state = JobState::Pending;
+        job.attempts = 0;
+        job.error = None;
+        job.failure_reason = None;
+        job.updated_at = now_secs();
+        push_log(job, LogLevel::Info, "Retry requested".to_string());
+        let retried = job.clone();
+        let snapshot = self.snapshot(&file);
+        drop(file);
+        self.persist(snapshot);
+        self.publish_state(id, Some(JobState::Pending));
+        self.wakeup.notify_one();
+        Ok(retried)
+    }
+
+    /// Cancels a pending job immediately, or flags a running job so the worker
+    /// stops at its next checkpoint.
+    pub fn cancel(&self, id: u64) -> Result<Job, QueueError> {
+        let mut file = self.lock();
+        let job = find_mut(&mut file, id)?;
+        match job.state {
+            JobState::Pending => {
+                job.state = JobState::Cancelled;
+                push_log(job, LogLevel::Warn, "Cancelled before start".to_string());
+            }
+            JobState::Running => {
+                job.cancel_requested = true;
+                push_log(job, LogLevel::Warn, "Cancellation requested".to_string());
+            }
+            state => return Err(QueueError::InvalidState { id, state }),
+        }
+        job.updated_at = now_secs();
+        let cancelled = job.clone();
+        let snapshot = self.snapshot(&file);
+        drop(file);
+        self.persist(snapshot);
+        self.publish_state(id, Some(cancelled.state));
+        Ok(cancelled)
+    }
+
+    pub fn is_cancel_requested(&self, id: u64) -> bool {
+        self.lock()
+            .jobs
+            .iter()
+            .any(|j| j.id == id && j.cancel_requested)
+    }
+
+    /// Deletes a finished job entry (output artifacts are left in place).
+    pub fn remove(&self, id: u64) -> Result<Job, QueueError> {
+        let mut file = self.lock();
+        let index = file
+            .jobs
+            .iter()
+            .position(|j| j.id == id)
+            .ok_or(QueueError::NotFound(id))?;
+        let state = file.jobs[index].state;
+        if !state.is_finished() {
+            return Err(QueueError::InvalidState { id, state });
+        }
+        let removed = file.jobs.remove(index);
+        let snapshot = self.snapshot(&file);
+        drop(file);
+        self.persist(snapshot);
+        self.publish_state(id, None);
+        Ok(removed)
+    }
+
+    /// Deletes every finished job and returns how many were removed.
+    pub fn remove_finished(&self) -> usize {
+        let mut file = self.lock();
+        let (finished, active): (Vec<Job>, Vec<Job>) = std::mem::take(&mut file.jobs)
+            .into_iter()
+            .partition(|j| j.state.is_finished());
+        file.jobs = active;
+        let snapshot = (!finished.is_empty()).then(|| self.snapshot(&file));
+        drop(file);
+        if let Some(snapshot) = snapshot {
+            self.persist(snapshot);
+        }
+        for job in &finished {
+            self.publish_state(job.id, None);
+        }
+        finished.len()
+    }
+
+    pub fn counts(&self) -> QueueCounts {
+        let file = self.lock();
+        let mut counts = QueueCounts::default();
+        for job in &file.jobs {
+            match job.state {
+                JobState::Pending => counts.pending += 1,
+                JobState::Running => counts.running += 1,
+                JobState::Succeeded => counts.succeeded += 1,
+                JobState::Failed => counts.failed += 1,
+                JobState::Cancelled => counts.cancelled += 1,
+            }
+        }
+        counts
+    }
+
+    /// The event bus job transitions are published on.
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    fn publish_state(&self, job_id: u64, state: Option<JobState>) {
+        self.events.publish(JobEvent::State { job_id, state });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueFile> {
+        // A panic while holding the lock leaves the data intact; keep serving it.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Serializes the queue; call with the lock held and [`Self::persist`]
+    /// the result after releasing it, so API requests and the watcher do not
+    /// wait for the disk.
+    fn snapshot(&self, file: &QueueFile) -> Snapshot {
+        Snapshot {
+            generation: self.generation.fetch_add(1, Ordering::Relaxed) + 1,
+            json: serde_json::to_vec(file),
+        }
+    }
+
+    /// Writes a snapshot atomically unless a newer one is already on disk.
+    /// Failures are logged rather than propagated so a full or read-only
+    /// `/config` degrades to an in-memory queue.
+    fn persist(&self, snapshot: Snapshot) {
+        let mut written = self.written.lock().unwrap_or_else(|e| e.into_inner());
+        if snapshot.generation <= *written {
+            return;
+        }
+        let result = snapshot
+            .json
+            .map_err(anyhow::Error::from)
+            .and_then(|json| write_atomic(&self.path, &json));
+        if let Err(e) = result {
+            log::error!("Queue: failed to persist {:?}: {}", self.path, e);
+        }
+        *written = snapshot.generation;
+        *self.persisted_at.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+}
+
+fn find_mut(file: &mut QueueFile, id: u64) -> Result<&mut Job, QueueError> {
+    file.jobs
+        .iter_mut()
+        .find(|j| j.id == id)
+        .ok_or(QueueError::NotFound(id))
+}
+
+/// Removes the oldest finished jobs beyond `retention` and returns their ids.
+/// Their sources are remembered while they still exist.
+fn prune(file: &mut QueueFile, retention: usize) -> Vec<u64> {
+    let mut finished: Vec<(u64, u64)> = file
+        .jobs
+        .iter()
+        .filter(|j| j.state.is_finished())
+        .map(|j| (j.updated_at, j.id))
+        .collect();
+    if finished.len() <= retention {
+        return Vec::new();
+    }
+    finished.sort_unstable();
+    let pruned: Vec<u64> = finished[..finished.len() - retention]
+        .iter()
+        .map(|&(_, id)| id)
+        .collect();
+    let (removed, kept) = std::mem::take(&mut file.jobs)
+        .into_iter()
+        .partition(|j| pruned.contains(&j.id));
+    file.jobs = kept;
+    for job in removed {
+        file.pruned.insert(job.source, job.source_modified);
+    }
+    file.pruned.retain(|source, _| source.exists());
+    pruned
+}
+
+fn push_log(job: &mut Job, level: LogLevel, message: String) {
+    if job.log.len() >= MAX_LOG_ENTRIES {
+        job.log.remove(0);
+    }
+    job.log.push(LogEntry {
+        at: now_secs(),
+        level,
+        message,
+    });
+}
+
+fn write_atomic(path: &Path, json: &[u8]) -> anyhow::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    {
+        let mut out = std::fs::File::create(&tmp)?;
+        out.write_all(json)?;
+        out.write_all(b"\n")?;
+        out.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Newest modification time in the tree at `path`: a file changed inside a
+/// dropped folder does not touch the folder's own mtime.
+fn modified_secs(path: &Path) -> Option<u64> {
+    watcher::newest_modified(path)
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+}
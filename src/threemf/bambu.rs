@@ -0,0 +1,282 @@
+//! Bambu Studio / MakerWorld Project Metadata
+//!
+//! Bambu Studio (and MakerWorld downloads) add vendor parts next to the core
+//! model: `Metadata/model_settings.config` (objects and plates, XML),
+//! `Metadata/project_settings.config` (the print profile, JSON) and
+//! `Metadata/plate_N.png` renders. MakerWorld also stores designer and profile
+//! details as `<metadata>` entries in the root model (`ProfileTitle`,
+//! `ProfileUserName`, `DesignerUserId`, ...), which arrive here via
+//! [`ModelMetadata::other`].
+
+use super::reader::{attr, ModelMetadata, PackageReader};
+use quick_xml::events::Event;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const MODEL_SETTINGS_PART: &str = "Metadata/model_settings.config";
+const PROJECT_SETTINGS_PART: &str = "Metadata/project_settings.config";
+
+/// One build plate of a Bambu project.
+#[derive(Debug, Clone, Serialize)]
+pub struct Plate {
+    /// 1-based plate number (`plater_id`).
+    pub index: u32,
+    /// Plate name set in Bambu Studio; often empty.
+    pub name: Option<String>,
+    /// Rendered plate image, if the package contains one.
+    pub thumbnail: Option<String>,
+    /// Names of the objects placed on the plate.
+    pub objects: Vec<String>,
+}
+
+/// Print profile summary from `project_settings.config`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrintProfile {
+    pub printer: Option<String>,
+    pub process: Option<String>,
+    pub nozzle_diameter: Option<String>,
+    pub layer_height: Option<String>,
+    pub filament_types: Vec<String>,
+    pub supports: bool,
+}
+
+/// Vendor metadata of a Bambu Studio / MakerWorld project.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BambuProject {
+    /// `Application` metadata, e.g. `BambuStudio-01.09.07.52`.
+    pub application: Option<String>,
+    /// MakerWorld print profile title and author (distinct from the design).
+    pub profile_title: Option<String>,
+    pub profile_author: Option<String>,
+    pub plates: Vec<Plate>,
+    pub profile: Option<PrintProfile>,
+    /// Manyfold tags derived from the print profile.
+    pub tags: Vec<String>,
+}
+
+/// Reads the vendor parts. Returns `None` for packages that carry no Bambu
+/// parts or metadata at all.
+pub fn read_project(
+    package: &mut PackageReader,
+    metadata: &ModelMetadata,
+) -> anyhow::Result<Option<BambuProject>> {
+    let model_settings = package.read_part(MODEL_SETTINGS_PART)?;
+    let project_settings = package.read_part(PROJECT_SETTINGS_PART)?;
+    let application = metadata.other.get("Application").cloned();
+    let is_bambu = model_settings.is_some()
+        || project_settings.is_some()
+        || application
+            .as_deref()
+            .is_some_and(|a| a.starts_with("BambuStudio") || a.starts_with("OrcaSlicer"))
+        || metadata.other.keys().any(|k| k.starts_with("BambuStudio:"));
+    if !is_bambu {
+        return Ok(None);
+    }
+
+    let mut project = BambuProject {
+        application,
+        profile_title: non_empty(metadata.other.get("ProfileTitle")),
+        profile_author: non_empty(metadata.other.get("ProfileUserName")),
+        ..BambuProject::default()
+    };
+
+    if let Some(data) = model_settings {
+        match parse_plates(&data) {
+            Ok(plates) => project.plates = plates,
+            Err(e) => log::warn!("3MF: ignoring unreadable {}: {}", MODEL_SETTINGS_PART, e),
+        }
+    }
+    // Packages without model_settings (or older exports) still ship renders.
+    let images: Vec<String> = package
+        .part_names()
+        .filter(|p| plate_image_index(p).is_some())
+        .map(str::to_string)
+        .collect();
+    for image in images {
+        let index = plate_image_index(&image).unwrap_or_default();
+        match project.plates.iter_mut().find(|p| p.index == index) {
+            Some(plate) => {
+                plate.thumbnail.get_or_insert(image);
+            }
+            None => project.plates.push(Plate {
+                index,
+                name: None,
+                thumbnail: Some(image),
+                objects: Vec::new(),
+            }),
+        }
+    }
+    project.plates.sort_by_key(|p| p.index);
+    // model_settings may reference renders that were stripped from the package.
+    for plate in &mut project.plates {
+        if plate
+            .thumbnail
+            .as_deref()
+            .is_some_and(|t| !package.has_part(t))
+        {
+            plate.thumbnail = None;
+        }
+    }
+
+    if let Some(data) = project_settings {
+        match serde_json::from_slice::<serde_json::Value>(&data) {
+            Ok(settings) => project.profile = Some(parse_profile(&settings)),
+            Err(e) => log::warn!("3MF: ignoring unreadable {}: {}", PROJECT_SETTINGS_PART, e),
+        }
+    }
+    project.tags = profile_tags(project.profile.as_ref());
+    Ok(Some(project))
+}
+
+/// Extracts plates (with their object names) from `model_settings.config`.
+fn parse_plates(data: &[u8]) -> Result<Vec<Plate>, String> {
+    let mut reader = quick_xml::Reader::from_reader(data);
+    let mut buf = Vec::new();
+    let mut object_names: BTreeMap<String, String> = BTreeMap::new();
+    let mut plates = Vec::new();
+
+    // Element context: the object id being read, or the plate being built
+    // (with object ids of its instances).
+    let mut object: Option<String> = None;
+    let mut in_part = false;
+    let mut plate: Option<(Plate, Vec<String>)> = None;
+    let mut in_instance = false;
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| e.to_string())?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let empty = matches!(event, Event::Empty(_));
+                match e.local_name().as_ref() {
+                    b"object" if !empty => object = attr(e, b"id"),
+                    b"part" if !empty => in_part = true,
+                    b"plate" if !empty => {
+                        let plate_info = Plate {
+                            index: 0,
+                            name: None,
+                            thumbnail: None,
+                            objects: Vec::new(),
+                        };
+                        plate = Some((plate_info, Vec::new()));
+                    }
+                    b"model_instance" if !empty => in_instance = true,
+                    b"metadata" => {
+                        let key = attr(e, b"key").unwrap_or_default();
+                        let value = attr(e, b"value").unwrap_or_default();
+                        if let Some((current, ids)) = plate.as_mut() {
+                            match (in_instance, key.as_str()) {
+                                (true, "object_id") => ids.push(value),
+                                (false, "plater_id") => current.index = value.parse().unwrap_or(0),
+                                (false, "plater_name") => current.name = non_empty(Some(&value)),
+                                (false, "thumbnail_file") => {
+                                    current.thumbnail = non_empty(Some(&value))
+                                        .map(|t| super::reader::normalize_part_name(&t))
+                                }
+                                _ => {}
+                            }
+                        } else if let (Some(id), false, "name") =
+                            (object.as_ref(), in_part, key.as_str())
+                        {
+                            object_names.insert(id.clone(), value);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(ref e) => match e.local_name().as_ref() {
+                b"object" => object = None,
+                b"part" => in_part = false,
+                b"model_instance" => in_instance = false,
+                b"plate" => {
+                    if let Some((mut current, ids)) = plate.take() {
+                        current.objects = ids
+                            .iter()
+                            .map(|id| object_names.get(id).cloned().unwrap_or_else(|| id.clone()))
+                            .collect();
+                        current.objects.dedup();
+                        plates.push(current);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(plates)
+}
+
+/// Summarises the print profile. Bambu stores most values as strings and
+/// per-extruder values as arrays of strings.
+fn parse_profile(settings: &serde_json::Value) -> PrintProfile {
+    let first = |key: &str| -> Option<String> {
+        match settings.get(key)? {
+            serde_json::Value::String(s) => non_empty(Some(s)),
+            serde_json::Value::Array(items) => items
+                .iter()
+                .find_map(|v| non_empty(v.as_str().map(str::to_string).as_ref())),
+            other => Some(other.to_string()),
+        }
+    };
+    let mut filament_types: Vec<String> = match settings.get("filament_type") {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| non_empty(v.as_str().map(str::to_string).as_ref()))
+            .collect(),
+        Some(serde_json::Value::String(s)) => non_empty(Some(s)).into_iter().collect(),
+        _ => Vec::new(),
+    };
+    filament_types.sort();
+    filament_types.dedup();
+
+    PrintProfile {
+        printer: first("printer_model").or_else(|| first("printer_settings_id")),
+        process: first("print_settings_id"),
+        nozzle_diameter: first("nozzle_diameter"),
+        layer_height: first("layer_height"),
+        filament_types,
+        supports: matches!(first("enable_support").as_deref(), Some("1") | Some("true")),
+    }
+}
+
+/// Manyfold tags for the print profile: printer, nozzle, layer height,
+/// filament types and whether supports are needed.
+fn profile_tags(profile: Option<&PrintProfile>) -> Vec<String> {
+    let Some(profile) = profile else {
+        return Vec::new();
+    };
+    let mut tags = Vec::new();
+    if let Some(printer) = &profile.printer {
+        tags.push(printer.clone());
+    }
+    if let Some(nozzle) = &profile.nozzle_diameter {
+        tags.push(format!("{} mm nozzle", nozzle));
+    }
+    if let Some(layer) = &profile.layer_height {
+        tags.push(format!("{} mm layer", layer));
+    }
+    tags.extend(profile.filament_types.iter().cloned());
+    if profile.supports {
+        tags.push("supports".to_string());
+    }
+    tags
+}
+
+/// `Metadata/plate_3.png` -> 3. Small variants and pick/top maps are not
+/// plate thumbnails.
+fn plate_image_index(part: &str) -> Option<u32> {
+    part.strip_prefix("Metadata/plate_")?
+        .strip_suffix(".png")?
+        .parse()
+        .ok()
+}
+
+fn non_empty(value: Option<&String>) -> Option<String> {
+    value
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
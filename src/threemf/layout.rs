@@ -0,0 +1,158 @@
+//! Build Plate Arrangement
+//!
+//! Places objects side by side on one or more build plates so merged inputs
+//! do not open as a pile of overlapping parts. Objects are packed by their
+//! XY bounding boxes in rows (shelves), tallest footprint first; when a plate
+//! is full the next one is started. Each plate's packed block is centred on
+//! the bed, and every object is dropped onto the bed (`z = 0`).
+//!
+//! Bambu Studio / OrcaSlicer lay multiple plates out in world space as a grid
+//! of `ceil(sqrt(n))` columns with a gap of one fifth of the bed between
+//! plates; build item transforms use the same coordinates so each object
+//! lands on its plate.
+
+use crate::geometry::Bounds;
+
+/// Clearance between neighbouring objects, in millimetres.
+const SPACING: f32 = 5.0;
+/// Gap between plates as a fraction of the bed size (Bambu Studio's
+/// `LOGICAL_PART_PLATE_GAP`).
+const PLATE_GAP: f32 = 0.2;
+
+/// Where one object goes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// 1-based plate number.
+    pub plate: u32,
+    /// Translation applied by the build item transform.
+    pub translation: [f32; 3],
+    /// The footprint is larger than the bed; the object sits alone on its
+    /// plate and overhangs it.
+    pub oversized: bool,
+}
+
+/// Result of [`arrange`]: one placement per object, in input order.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub placements: Vec<Placement>,
+    pub plate_count: u32,
+}
+
+impl Layout {
+    /// Object indices (0-based) on `plate`, in input order.
+    pub fn objects_on(&self, plate: u32) -> impl Iterator<Item = usize> + '_ {
+        self.placements
+            .iter()
+            .enumerate()
+            .filter(move |(_, p)| p.plate == plate)
+            .map(|(idx, _)| idx)
+    }
+}
+
+/// A slot assigned during packing, relative to the plate's corner.
+struct Slot {
+    plate: usize,
+    x: f32,
+    y: f32,
+    oversized: bool,
+}
+
+/// Arranges objects with the given bounds (`None` for empty meshes) on beds
+/// of `bed` millimetres (width, depth).
+pub fn arrange(bounds: &[Option<Bounds>], bed: [f32; 2]) -> Layout {
+    let footprint = |idx: usize| -> [f32; 2] {
+        bounds[idx].map_or([0.0, 0.0], |b| {
+            let size = b.size();
+            [size[0], size[1]]
+        })
+    };
+
+    let mut order: Vec<usize> = (0..bounds.len()).collect();
+    order.sort_by(|&a, &b| footprint(b)[1].total_cmp(&footprint(a)[1]));
+
+    let mut slots: Vec<Option<Slot>> = (0..bounds.len()).map(|_| None).collect();
+    // Per plate: used extent (width, depth) of the packed block.
+    let mut extents: Vec<[f32; 2]> = Vec::new();
+    // Packing state of the plate being filled: cursor x, shelf y, shelf depth.
+    let mut open: Option<(usize, f32, f32, f32)> = None;
+
+    for idx in order {
+        let [w, d] = footprint(idx);
+        if w > bed[0] || d > bed[1] {
+            extents.push([w, d]);
+            slots[idx] = Some(Slot {
+                plate: extents.len() - 1,
+                x: 0.0,
+                y: 0.0,
+                oversized: true,
+            });
+            continue;
+        }
+
+        let (plate, x, y) = match open {
+            Some((plate, cursor, shelf_y, _)) if cursor + w <= bed[0] => (plate, cursor, shelf_y),
+            Some((plate, _, shelf_y, shelf_d)) if shelf_y + shelf_d + SPACING + d <= bed[1] => {
+                (plate, 0.0, shelf_y + shelf_d + SPACING)
+            }
+            _ => {
+                extents.push([0.0, 0.0]);
+                (extents.len() - 1, 0.0, 0.0)
+            }
+        };
+        let shelf_d = match open {
+            Some((open_plate, _, shelf_y, shelf_d)) if open_plate == plate && shelf_y == y => {
+                shelf_d.max(d)
+            }
+            _ => d,
+        };
+        open = Some((plate, x + w + SPACING, y, shelf_d));
+        let extent = &mut extents[plate];
+        extent[0] = extent[0].max(x + w);
+        extent[1] = extent[1].max(y + d);
+        slots[idx] = Some(Slot {
+            plate,
+            x,
+            y,
+            oversized: false,
+        });
+    }
+
+    let plate_count = extents.len().max(1);
+    let columns = (plate_count as f32).sqrt().ceil() as usize;
+    let pitch = [bed[0] + bed[0] * PLATE_GAP, bed[1] + bed[1] * PLATE_GAP];
+    let placements = slots
+        .into_iter()
+        .zip(bounds)
+        .map(|(slot, bounds)| {
+            let slot = slot.unwrap_or(Slot {
+                plate: 0,
+                x: 0.0,
+                y: 0.0,
+                oversized: false,
+            });
+            let extent = extents.get(slot.plate).copied().unwrap_or([0.0, 0.0]);
+            let origin = [
+                (slot.plate % columns) as f32 * pitch[0],
+                -((slot.plate / columns) as f32) * pitch[1],
+            ];
+            // Centre the packed block on the bed.
+            let offset = [(bed[0] - extent[0]) / 2.0, (bed[1] - extent[1]) / 2.0];
+            let min = bounds.map_or([0.0; 3], |b| b.min);
+            Placement {
+                plate: slot.plate as u32 + 1,
+                translation: [
+                    origin[0] + offset[0] + slot.x - min[0],
+                    origin[1] + offset[1] + slot.y - min[1],
+                    // `+ 0.0` keeps objects already on the bed at `0`, not `-0`.
+                    -min[2] + 0.0,
+                ],
+                oversized: slot.oversized,
+            }
+        })
+        .collect();
+
+    Layout {
+        placements,
+        plate_count: plate_count as u32,
+    }
+}
@@ -0,0 +1,263 @@
+//! 3MF Packaging
+//!
+//! Governance: .agent/skills/3mf_specification/SKILL.md
+//!
+//! Writes OPC-compliant 3MF packages from indexed meshes:
+//! `[Content_Types].xml`, `_rels/.rels`, `3D/3dmodel.model`, an optional
+//! `Metadata/thumbnail.png` and, for Bambu
+//! Studio / OrcaSlicer, `Metadata/model_settings.config`. Existing packages
+//! (including Bambu / MakerWorld project parts) are inspected through
+//! [`PackageReader`].
+
+mod bambu;
+mod layout;
+mod model;
+mod reader;
+mod settings;
+mod xml;
+mod zip;
+
+pub(crate) use reader::attr;
+pub use reader::{read_package, PackageInfo, PackageReader, ThreeMfError};
+
+use crate::geometry::{ConvertOptions, Material, MeshSource, ProgressSink};
+use crate::progress::Stage;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+const CONTENT_TYPES_HEAD: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+  <Default Extension="model" ContentType="application/vnd.ms-package.3dmanufacturing-3dmodel+xml"/>
+  <Default Extension="config" ContentType="text/xml"/>
+"#;
+
+const ROOT_RELS_HEAD: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Target="/3D/3dmodel.model" Id="rel0" Type="http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel"/>
+"#;
+
+const THUMBNAIL_REL_TYPE: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/thumbnail";
+/// Larger images are not embedded; a package thumbnail is a preview, not an
+/// archive of the original render.
+const MAX_THUMBNAIL_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Model entries estimated above this size get ZIP64 headers up front. Half of
+/// the 32-bit limit leaves ample room for estimation error.
+const LARGE_ENTRY_THRESHOLD: u64 = u32::MAX as u64 / 2;
+
+/// Result of [`write_package`].
+#[derive(Debug, Clone)]
+pub struct WrittenPackage {
+    pub bytes: u64,
+    /// Why `options.thumbnail` was not embedded; `None` if it was, or if no
+    /// thumbnail was given.
+    pub thumbnail_skipped: Option<String>,
+}
+
+/// Writes `meshes` as one 3MF package (one object per mesh, or one assembly
+/// object when `options.assembly` is set, arranged on `options.bed_size`
+/// plates). `materials` are the colours the meshes' triangle materials refer
+/// to.
+pub fn write_package(
+    output: &Path,
+    meshes: &[&dyn MeshSource],
+    materials: &[Material],
+    options: &ConvertOptions,
+    progress: &dyn ProgressSink,
+) -> anyhow::Result<WrittenPackage> {
+    let file = File::create(output)?;
+    let mut zip = zip::ZipWriter::new(BufWriter::new(file), options.compression_level);
+    let steps = if options.bambu_settings { 4.0 } else { 3.0 };
+    let (thumbnail, thumbnail_skipped) = match &options.thumbnail {
+        Some(path) => match load_thumbnail(path)? {
+            Ok(thumbnail) => (Some(thumbnail), None),
+            Err(reason) => {
+                log::warn!("3MF: not embedding thumbnail {:?}: {}", path, reason);
+                (None, Some(reason))
+            }
+        },
+        None => (None, None),
+    };
+
+    progress.progress(Stage::Zipping, 0.0);
+    let format = thumbnail.as_ref().map(|(format, _)| format);
+    zip.add_file("[Content_Types].xml", content_types(format).as_bytes())?;
+    zip.add_file("_rels/.rels", root_rels(format).as_bytes())?;
+    if let Some((format, data)) = &thumbnail {
+        zip.add_file(&format.part(), data)?;
+    }
+    progress.progress(Stage::Zipping, 1.0 / steps);
+
+    progress.check_cancelled()?;
+    let mut bounds = meshes
+        .iter()
+        .map(|m| m.bounds())
+        .collect::<io::Result<Vec<_>>>()?;
+    let assembly = options.assembly.as_deref();
+    let mut names: Vec<&str> = meshes.iter().map(|m| m.name()).collect();
+    if let Some(name) = assembly {
+        // Parts keep their relative positions; only the assembly is placed.
+        let union = bounds.iter().flatten().copied().reduce(|mut all, b| {
+            all.include(b.min);
+            all.include(b.max);
+            all
+        });
+        bounds = vec![union];
+        names = vec![name];
+    }
+    let layout = layout::arrange(&bounds, options.bed_size);
+    for (name, placement) in names.iter().zip(&layout.placements) {
+        if placement.oversized {
+            log::warn!(
+                "3MF: {} is larger than the {}x{} mm bed; placed alone on plate {}",
+                name,
+                options.bed_size[0],
+                options.bed_size[1],
+                placement.plate
+            );
+        }
+    }
+
+    let large = model::estimated_size(meshes) >= LARGE_ENTRY_THRESHOLD;
+    let entry = zip.start_file("3D/3dmodel.model", large)?;
+    let entry = model::write_model(
+        BufWriter::with_capacity(64 * 1024, entry),
+        meshes,
+        materials,
+        &layout,
+        assembly,
+    )?;
+    entry.into_inner().map_err(|e| e.into_error())?.finish()?;
+    progress.progress(Stage::Zipping, 2.0 / steps);
+
+    if options.bambu_settings {
+        let plate_thumbnail = format.filter(|f| **f == ImageFormat::Png).map(|f| f.part());
+        let config_xml =
+            settings::model_settings_xml(meshes, &layout, assembly, plate_thumbnail.as_deref());
+        zip.add_file("Metadata/model_settings.config", config_xml.as_bytes())?;
+        progress.progress(Stage::Zipping, 3.0 / steps);
+    }
+
+    let mut writer = zip.finish()?;
+    writer.flush()?;
+    let bytes = writer.get_ref().metadata()?.len();
+    progress.progress(Stage::Zipping, 1.0);
+    Ok(WrittenPackage {
+        bytes,
+        thumbnail_skipped,
+    })
+}
+
+/// 3MF object ids of the build items: one per mesh (`1..=n`), or the single
+/// assembly object `n + 1` that follows the part meshes.
+fn build_object_ids(mesh_count: usize, assembly: bool) -> Vec<u32> {
+    if assembly {
+        vec![mesh_count as u32 + 1]
+    } else {
+        (1..=mesh_count as u32).collect()
+    }
+}
+
+/// Resource id of the `<basematerials>` group, after the mesh objects and
+/// the optional assembly object.
+fn materials_id(mesh_count: usize) -> u32 {
+    mesh_count as u32 + 2
+}
+
+/// Image formats the 3MF core specification allows as package thumbnail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+impl ImageFormat {
+    /// Detects the format from the file signature.
+    fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageFormat::Jpeg)
+        } else {
+            None
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+        }
+    }
+
+    fn part(self) -> String {
+        format!("Metadata/thumbnail.{}", self.extension())
+    }
+}
+
+/// Reads the thumbnail image, or the reason it cannot be embedded. Images
+/// that are too large or not PNG/JPEG are skipped rather than failing the
+/// conversion.
+fn load_thumbnail(path: &Path) -> anyhow::Result<Result<(ImageFormat, Vec<u8>), String>> {
+    let size = std::fs::metadata(path)?.len();
+    if size > MAX_THUMBNAIL_SIZE {
+        return Ok(Err(format!("{} bytes, limit {}", size, MAX_THUMBNAIL_SIZE)));
+    }
+    let data = std::fs::read(path)?;
+    match ImageFormat::sniff(&data) {
+        Some(format) => Ok(Ok((format, data))),
+        None => Ok(Err("not a PNG or JPEG image".to_string())),
+    }
+}
+
+fn content_types(thumbnail: Option<&ImageFormat>) -> String {
+    let mut xml = CONTENT_TYPES_HEAD.to_string();
+    if let Some(format) = thumbnail {
+        xml.push_str(&format!(
+            "  <Default Extension=\"{}\" ContentType=\"{}\"/>\n",
+            format.extension(),
+            format.content_type()
+        ));
+    }
+    xml.push_str("</Types>\n");
+    xml
+}
+
+fn root_rels(thumbnail: Option<&ImageFormat>) -> String {
+    let mut xml = ROOT_RELS_HEAD.to_string();
+    if let Some(format) = thumbnail {
+        xml.push_str(&format!(
+            "  <Relationship Target=\"/{}\" Id=\"rel1\" Type=\"{}\"/>\n",
+            format.part(),
+            THUMBNAIL_REL_TYPE
+        ));
+    }
+    xml.push_str("</Relationships>\n");
+    xml
+}
+
+/// Escapes text for use inside an XML attribute value.
+pub(crate) fn xml_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,147 @@
+//! `3D/3dmodel.model` generation (3MF Core Specification).
+
+use super::layout::Layout;
+use super::xml::XmlWriter;
+use super::{build_object_ids, materials_id};
+use crate::geometry::{Material, MeshSource};
+use std::io::{self, Write};
+
+const CORE_NAMESPACE: &str = "http://schemas.microsoft.com/3dmanufacturing/core/2015/02";
+
+/// Upper-bound-ish size of one `<vertex .../>` / `<triangle .../>` line.
+const VERTEX_LINE_BYTES: u64 = 80;
+const TRIANGLE_LINE_BYTES: u64 = 70;
+
+/// Rough size of the model document, used to decide on ZIP64 headers.
+pub fn estimated_size(meshes: &[&dyn MeshSource]) -> u64 {
+    meshes
+        .iter()
+        .map(|m| {
+            m.vertex_count() as u64 * VERTEX_LINE_BYTES
+                + m.triangle_count() as u64 * TRIANGLE_LINE_BYTES
+        })
+        .sum()
+}
+
+/// 3MF affine transform (row-major 4x3, last row translation) for a pure
+/// translation.
+pub fn translation_matrix(t: [f32; 3]) -> [f32; 12] {
+    [
+        1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, t[0], t[1], t[2],
+    ]
+}
+
+/// Streams the core model document into `out`. Mesh object ids are 1-based
+/// in mesh order; vertices and triangles are never buffered as a whole. With
+/// `assembly`, one more object named after it references every mesh through
+/// `<components>` and is the only build item. Build items carry the
+/// translation from `layout`. Non-empty `materials` are written as one
+/// `<basematerials>` group referenced by the meshes' triangle materials.
+pub fn write_model<W: Write>(
+    out: W,
+    meshes: &[&dyn MeshSource],
+    materials: &[Material],
+    layout: &Layout,
+    assembly: Option<&str>,
+) -> io::Result<W> {
+    let mut xml = XmlWriter::new(out);
+    xml.declaration()?;
+    xml.tag("model")?;
+    xml.attr("unit", "millimeter")?;
+    xml.attr("xml:lang", "en-US")?;
+    xml.attr("xmlns", CORE_NAMESPACE)?;
+    xml.open()?;
+    xml.start("resources")?;
+
+    let pid = materials_id(meshes.len());
+    if !materials.is_empty() {
+        xml.tag("basematerials")?;
+        xml.attr_u32("id", pid)?;
+        xml.open()?;
+        for material in materials {
+            let [r, g, b, a] = material.color;
+            xml.tag("base")?;
+            xml.attr("name", &material.name)?;
+            xml.attr(
+                "displaycolor",
+                &format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a),
+            )?;
+            xml.close_empty()?;
+        }
+        xml.end("basematerials")?;
+    }
+
+    for (idx, mesh) in meshes.iter().enumerate() {
+        let triangle_materials = mesh.triangle_materials();
+        xml.tag("object")?;
+        xml.attr_u32("id", idx as u32 + 1)?;
+        xml.attr("name", mesh.name())?;
+        xml.attr("type", "model")?;
+        // Triangles without a material fall back to the object default.
+        if let Some(default) = triangle_materials.iter().flatten().next() {
+            xml.attr_u32("pid", pid)?;
+            xml.attr_u32("pindex", *default)?;
+        }
+        xml.open()?;
+        xml.start("mesh")?;
+
+        xml.start("vertices")?;
+        mesh.for_each_vertex(&mut |[x, y, z]| {
+            xml.tag("vertex")?;
+            xml.attr_f32("x", x)?;
+            xml.attr_f32("y", y)?;
+            xml.attr_f32("z", z)?;
+            xml.close_empty()
+        })?;
+        xml.end("vertices")?;
+
+        xml.start("triangles")?;
+        let mut triangle = 0;
+        mesh.for_each_triangle(&mut |[v1, v2, v3]| {
+            xml.tag("triangle")?;
+            xml.attr_u32("v1", v1)?;
+            xml.attr_u32("v2", v2)?;
+            xml.attr_u32("v3", v3)?;
+            if let Some(Some(material)) = triangle_materials.get(triangle) {
+                xml.attr_u32("pid", pid)?;
+                xml.attr_u32("p1", *material)?;
+            }
+            triangle += 1;
+            xml.close_empty()
+        })?;
+        xml.end("triangles")?;
+
+        xml.end("mesh")?;
+        xml.end("object")?;
+    }
+
+    let build_ids = build_object_ids(meshes.len(), assembly.is_some());
+    if let Some(name) = assembly {
+        xml.tag("object")?;
+        xml.attr_u32("id", build_ids[0])?;
+        xml.attr("name", name)?;
+        xml.attr("type", "model")?;
+        xml.open()?;
+        xml.start("components")?;
+        for idx in 0..meshes.len() {
+            xml.tag("component")?;
+            xml.attr_u32("objectid", idx as u32 + 1)?;
+            xml.close_empty()?;
+        }
+        xml.end("components")?;
+        xml.end("object")?;
+    }
+
+    xml.end("resources")?;
+    xml.start("build")?;
+    for (&id, placement) in build_ids.iter().zip(&layout.placements) {
+        xml.tag("item")?;
+        xml.attr_u32("objectid", id)?;
+        xml.attr_f32_list("transform", &translation_matrix(placement.translation))?;
+        xml.attr("printable", "1")?;
+        xml.close_empty()?;
+    }
+    xml.end("build")?;
+    xml.end("model")?;
+    Ok(xml.into_inner())
+}
@@ -0,0 +1,446 @@
+//! 3MF Package Reader
+//!
+//! Governance: .agent/skills/3mf_specification/SKILL.md
+//!
+//! Inspects existing 3MF packages without extracting them: follows
+//! `_rels/.rels` to the root model part, streams it with `quick-xml` to collect
+//! `<metadata>` values and object/build counts, and lists embedded thumbnails.
+//! Geometry is skipped, so memory use does not depend on mesh size. Bambu
+//! Studio / MakerWorld vendor parts are read by [`super::bambu`].
+
+use super::bambu::{self, BambuProject};
+use super::MAX_THUMBNAIL_SIZE;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::XmlVersion;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+const RELS_PART: &str = "_rels/.rels";
+/// Used when a package has no (readable) root relationships part.
+const DEFAULT_MODEL_PART: &str = "3D/3dmodel.model";
+const MODEL_REL_TYPE: &str = "http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel";
+const THUMBNAIL_REL_TYPE: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/thumbnail";
+/// Upper bound for small parts read fully into memory (relationships, configs).
+const MAX_SMALL_PART: u64 = 16 * 1024 * 1024;
+
+/// Why a 3MF package could not be read.
+#[derive(Debug)]
+pub enum ThreeMfError {
+    /// Not a readable ZIP container.
+    InvalidZip(String),
+    /// The root model part referenced by the relationships does not exist.
+    MissingModel(String),
+    /// A part is not well-formed XML.
+    InvalidXml { part: String, message: String },
+}
+
+impl ThreeMfError {
+    /// Stable identifier recorded as the job's failure reason.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ThreeMfError::InvalidZip(_) => "threemf_invalid_zip",
+            ThreeMfError::MissingModel(_) => "threemf_missing_model",
+            ThreeMfError::InvalidXml { .. } => "threemf_invalid_xml",
+        }
+    }
+}
+
+impl fmt::Display for ThreeMfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThreeMfError::InvalidZip(message) => {
+                write!(f, "not a valid 3MF/ZIP package: {}", message)
+            }
+            ThreeMfError::MissingModel(part) => write!(f, "model part {:?} is missing", part),
+            ThreeMfError::InvalidXml { part, message } => {
+                write!(f, "{} is not valid XML: {}", part, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ThreeMfError {}
+
+/// Well-known core metadata (3MF Core Specification, chapter 2.1).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModelMetadata {
+    pub title: Option<String>,
+    pub designer: Option<String>,
+    pub license: Option<String>,
+    pub description: Option<String>,
+    /// Every other `<metadata>` entry by name (e.g. `Application`, `CreationDate`).
+    pub other: BTreeMap<String, String>,
+}
+
+/// Image part embedded in the package.
+#[derive(Debug, Clone, Serialize)]
+pub struct Thumbnail {
+    /// Part name inside the package, without leading `/`.
+    pub part: String,
+    /// Uncompressed size in bytes.
+    pub size: u64,
+    /// Referenced as the package thumbnail by `_rels/.rels`.
+    pub primary: bool,
+}
+
+/// Summary of an existing 3MF package.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PackageInfo {
+    /// Root model part, without leading `/`.
+    pub model_part: String,
+    /// `unit` attribute of `<model>` (millimeter if absent).
+    pub unit: String,
+    pub metadata: ModelMetadata,
+    /// `<object>` resources in the root model.
+    pub object_count: usize,
+    /// `<item>` entries in `<build>`.
+    pub build_item_count: usize,
+    /// `requiredextensions` namespaces; consumers must understand these to
+    /// interpret the geometry.
+    pub required_extensions: Vec<String>,
+    /// Primary thumbnail first, then other images in package order.
+    pub thumbnails: Vec<Thumbnail>,
+    /// Bambu Studio / MakerWorld project details, if present.
+    pub bambu: Option<BambuProject>,
+}
+
+impl PackageInfo {
+    /// The thumbnail best suited as a preview: the declared package thumbnail,
+    /// otherwise the largest embedded image.
+    pub fn preview(&self) -> Option<&Thumbnail> {
+        self.thumbnails
+            .iter()
+            .find(|t| t.primary)
+            .or_else(|| self.thumbnails.iter().max_by_key(|t| t.size))
+    }
+
+    /// Model title for Manyfold: the core `Title`, else the MakerWorld
+    /// profile title.
+    pub fn title(&self) -> Option<&str> {
+        self.metadata
+            .title
+            .as_deref()
+            .or_else(|| self.bambu.as_ref()?.profile_title.as_deref())
+    }
+
+    /// Model creator for Manyfold: the core `Designer`, else the MakerWorld
+    /// profile author.
+    pub fn creator(&self) -> Option<&str> {
+        self.metadata
+            .designer
+            .as_deref()
+            .or_else(|| self.bambu.as_ref()?.profile_author.as_deref())
+    }
+
+    pub fn license(&self) -> Option<&str> {
+        self.metadata.license.as_deref()
+    }
+
+    /// Tags derived from the print profile (empty for non-Bambu packages).
+    pub fn tags(&self) -> &[String] {
+        self.bambu.as_ref().map_or(&[], |b| b.tags.as_slice())
+    }
+}
+
+/// Open 3MF package for random-access reads.
+pub struct PackageReader {
+    archive: ZipArchive<BufReader<File>>,
+}
+
+impl PackageReader {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path)?;
+        let archive = ZipArchive::new(BufReader::new(file))
+            .map_err(|e| ThreeMfError::InvalidZip(e.to_string()))?;
+        Ok(Self { archive })
+    }
+
+    /// Reads a small part (relationships, configs, thumbnails) into memory.
+    /// Returns `None` if the part does not exist.
+    pub fn read_part(&mut self, part: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(index) = self.archive.index_for_name(part) else {
+            return Ok(None);
+        };
+        let entry = self.archive.by_index(index)?;
+        if entry.size() > MAX_SMALL_PART {
+            anyhow::bail!(
+                "{} is {} bytes, too large to read into memory",
+                part,
+                entry.size()
+            );
+        }
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.take(MAX_SMALL_PART).read_to_end(&mut data)?;
+        Ok(Some(data))
+    }
+
+    pub fn has_part(&self, part: &str) -> bool {
+        self.archive.index_for_name(part).is_some()
+    }
+
+    /// Part names in package order.
+    pub fn part_names(&self) -> impl Iterator<Item = &str> {
+        self.archive.file_names()
+    }
+
+    /// Streams an image part into `dest` and returns the number of bytes
+    /// written, or the reason it was skipped. Images above the thumbnail limit
+    /// are refused before anything is written; the declared size can lie, so
+    /// the copy itself is bounded as well.
+    pub fn extract_image(
+        &mut self,
+        part: &str,
+        dest: &Path,
+    ) -> anyhow::Result<Result<u64, String>> {
+        let entry = self
+            .archive
+            .by_name(part)
+            .map_err(|e| anyhow::anyhow!("{}: {}", part, e))?;
+        if entry.size() > MAX_THUMBNAIL_SIZE {
+            return Ok(Err(format!(
+                "{} bytes, limit {}",
+                entry.size(),
+                MAX_THUMBNAIL_SIZE
+            )));
+        }
+        let mut out = io::BufWriter::new(File::create(dest)?);
+        let written = io::copy(&mut entry.take(MAX_THUMBNAIL_SIZE + 1), &mut out)?;
+        io::Write::flush(&mut out)?;
+        if written > MAX_THUMBNAIL_SIZE {
+            drop(out);
+            std::fs::remove_file(dest)?;
+            return Ok(Err(format!("more than {} bytes", MAX_THUMBNAIL_SIZE)));
+        }
+        Ok(Ok(written))
+    }
+
+    /// Collects metadata, counts and thumbnails.
+    pub fn info(&mut self) -> anyhow::Result<PackageInfo> {
+        let rels = self.root_relationships()?;
+        let model_part = rels
+            .iter()
+            .find(|r| r.kind == MODEL_REL_TYPE)
+            .map(|r| r.target.clone())
+            .unwrap_or_else(|| DEFAULT_MODEL_PART.to_string());
+        let primary_thumbnail = rels
+            .iter()
+            .find(|r| r.kind == THUMBNAIL_REL_TYPE)
+            .map(|r| r.target.clone());
+
+        let mut info = PackageInfo {
+            unit: "millimeter".to_string(),
+            ..PackageInfo::default()
+        };
+        {
+            let entry = self
+                .archive
+                .by_name(&model_part)
+                .map_err(|_| ThreeMfError::MissingModel(model_part.clone()))?;
+            scan_model(BufReader::new(entry), &mut info).map_err(|message| {
+                ThreeMfError::InvalidXml {
+                    part: model_part.clone(),
+                    message,
+                }
+            })?;
+        }
+        info.model_part = model_part;
+
+        for index in 0..self.archive.len() {
+            let entry = self.archive.by_index_raw(index)?;
+            let part = entry.name().to_string();
+            if !is_image(&part) {
+                continue;
+            }
+            let primary = primary_thumbnail.as_deref() == Some(part.as_str());
+            let thumbnail = Thumbnail {
+                part,
+                size: entry.size(),
+                primary,
+            };
+            if primary {
+                info.thumbnails.insert(0, thumbnail);
+            } else {
+                info.thumbnails.push(thumbnail);
+            }
+        }
+        info.bambu = bambu::read_project(self, &info.metadata)?;
+        Ok(info)
+    }
+
+    /// Parses `_rels/.rels`; a missing part yields no relationships.
+    fn root_relationships(&mut self) -> anyhow::Result<Vec<Relationship>> {
+        let Some(data) = self.read_part(RELS_PART)? else {
+            return Ok(Vec::new());
+        };
+        parse_relationships(&data).map_err(|message| {
+            ThreeMfError::InvalidXml {
+                part: RELS_PART.to_string(),
+                message,
+            }
+            .into()
+        })
+    }
+}
+
+/// Reads a 3MF package summary in one call.
+pub fn read_package(path: &Path) -> anyhow::Result<PackageInfo> {
+    PackageReader::open(path)?.info()
+}
+
+struct Relationship {
+    target: String,
+    kind: String,
+}
+
+fn parse_relationships(data: &[u8]) -> Result<Vec<Relationship>, String> {
+    let mut reader = quick_xml::Reader::from_reader(data);
+    let mut buf = Vec::new();
+    let mut rels = Vec::new();
+    loop {
+        match reader
+            .read_event_into(&mut buf)
+            .map_err(|e| e.to_string())?
+        {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"Relationship" => {
+                if let (Some(target), Some(kind)) = (attr(&e, b"Target"), attr(&e, b"Type")) {
+                    rels.push(Relationship {
+                        target: normalize_part_name(&target),
+                        kind,
+                    });
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(rels)
+}
+
+/// Streams the model document, filling metadata and counts.
+fn scan_model<R: BufRead>(input: R, info: &mut PackageInfo) -> Result<(), String> {
+    let mut reader = quick_xml::Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut in_build = false;
+    // Name and accumulated text of the `<metadata>` element being read.
+    let mut metadata: Option<(String, String)> = None;
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| e.to_string())?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let empty = matches!(event, Event::Empty(_));
+                match e.local_name().as_ref() {
+                    b"model" => {
+                        if let Some(unit) = attr(e, b"unit") {
+                            info.unit = unit;
+                        }
+                        if let Some(required) = attr(e, b"requiredextensions") {
+                            info.required_extensions =
+                                required.split_whitespace().map(str::to_string).collect();
+                        }
+                    }
+                    b"metadata" => {
+                        let name = attr(e, b"name").unwrap_or_default();
+                        if empty {
+                            record_metadata(&mut info.metadata, name, String::new());
+                        } else {
+                            metadata = Some((name, String::new()));
+                        }
+                    }
+                    b"object" => info.object_count += 1,
+                    b"build" => in_build = !empty,
+                    b"item" if in_build => info.build_item_count += 1,
+                    _ => {}
+                }
+            }
+            Event::Text(ref t) => {
+                if let Some((_, text)) = metadata.as_mut() {
+                    text.push_str(&t.decode().map_err(|e| e.to_string())?);
+                }
+            }
+            Event::CData(ref t) => {
+                if let Some((_, text)) = metadata.as_mut() {
+                    text.push_str(&t.decode().map_err(|e| e.to_string())?);
+                }
+            }
+            Event::GeneralRef(ref r) => {
+                if let Some((_, text)) = metadata.as_mut() {
+                    if let Some(c) = r.resolve_char_ref().map_err(|e| e.to_string())? {
+                        text.push(c);
+                    } else {
+                        let name = r.decode().map_err(|e| e.to_string())?;
+                        let resolved = quick_xml::escape::resolve_predefined_entity(&name);
+                        text.push_str(resolved.unwrap_or_default());
+                    }
+                }
+            }
+            Event::End(ref e) => match e.local_name().as_ref() {
+                b"metadata" => {
+                    if let Some((name, text)) = metadata.take() {
+                        record_metadata(&mut info.metadata, name, text);
+                    }
+                }
+                b"build" => in_build = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+fn record_metadata(metadata: &mut ModelMetadata, name: String, value: String) {
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return;
+    }
+    // Vendor entries keep their prefix (e.g. `BambuStudio:3mfVersion`) and
+    // land in `other`; only the unprefixed core names are promoted.
+    let slot = match name.as_str() {
+        "Title" => &mut metadata.title,
+        "Designer" => &mut metadata.designer,
+        "License" | "LicenseTerms" => &mut metadata.license,
+        "Description" => &mut metadata.description,
+        _ => {
+            metadata.other.insert(name, value);
+            return;
+        }
+    };
+    slot.get_or_insert(value);
+}
+
+/// Returns an unescaped attribute value by local name.
+pub(crate) fn attr(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| {
+            a.normalized_value(XmlVersion::Implicit1_0)
+                .ok()
+                .map(|v| v.into_owned())
+        })
+}
+
+/// OPC part names are absolute (`/3D/3dmodel.model`); ZIP entries are not.
+pub(crate) fn normalize_part_name(target: &str) -> String {
+    target.trim_start_matches('/').to_string()
+}
+
+pub(crate) fn is_image(part: &str) -> bool {
+    let lower = part.to_ascii_lowercase();
+    [".png", ".jpg", ".jpeg", ".webp", ".gif", ".bmp"]
+        .iter()
+        .any(|ext| lower.ends_with(ext))
+}
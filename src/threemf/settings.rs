@@ -0,0 +1,110 @@
+//! `Metadata/model_settings.config` generation (Bambu Studio / OrcaSlicer).
+
+use super::layout::Layout;
+use super::{build_object_ids, xml_escape};
+use crate::geometry::MeshSource;
+use std::fmt::Write;
+
+const IDENTITY_MATRIX: &str = "1 0 0 0 0 1 0 0 0 0 1 0 0 0 0 1";
+
+/// Builds the slicer settings document, with objects assigned to the plates
+/// chosen by `layout`. Each mesh is an object with a single part or, with
+/// `assembly`, a part of the one assembly object. `thumbnail` (a PNG part)
+/// becomes the thumbnail of plate 1 in Bambu Studio.
+pub fn model_settings_xml(
+    meshes: &[&dyn MeshSource],
+    layout: &Layout,
+    assembly: Option<&str>,
+    thumbnail: Option<&str>,
+) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<config>\n");
+
+    let build_ids = build_object_ids(meshes.len(), assembly.is_some());
+    match assembly {
+        Some(name) => {
+            write_object_head(&mut xml, build_ids[0], name);
+            for (idx, mesh) in meshes.iter().enumerate() {
+                write_part(&mut xml, idx as u32 + 1, mesh.name());
+            }
+            xml.push_str("  </object>\n");
+        }
+        None => {
+            for (mesh, &id) in meshes.iter().zip(&build_ids) {
+                write_object_head(&mut xml, id, mesh.name());
+                write_part(&mut xml, id, mesh.name());
+                xml.push_str("  </object>\n");
+            }
+        }
+    }
+
+    for plate in 1..=layout.plate_count {
+        xml.push_str("  <plate>\n");
+        let _ = writeln!(xml, "    <metadata key=\"plater_id\" value=\"{}\"/>", plate);
+        xml.push_str("    <metadata key=\"plater_name\" value=\"\"/>\n");
+        xml.push_str("    <metadata key=\"locked\" value=\"false\"/>\n");
+        if let (1, Some(part)) = (plate, thumbnail) {
+            let _ = writeln!(
+                xml,
+                "    <metadata key=\"thumbnail_file\" value=\"{}\"/>",
+                xml_escape(part)
+            );
+        }
+        for idx in layout.objects_on(plate) {
+            let id = build_ids[idx];
+            xml.push_str("    <model_instance>\n");
+            let _ = writeln!(xml, "      <metadata key=\"object_id\" value=\"{}\"/>", id);
+            xml.push_str("      <metadata key=\"instance_id\" value=\"0\"/>\n");
+            let _ = writeln!(
+                xml,
+                "      <metadata key=\"identify_id\" value=\"{}\"/>",
+                100 + id
+            );
+            xml.push_str("    </model_instance>\n");
+        }
+        xml.push_str("  </plate>\n");
+    }
+
+    xml.push_str("  <assemble>\n");
+    for (idx, placement) in layout.placements.iter().enumerate() {
+        let [x, y, z] = placement.translation;
+        let _ = writeln!(
+            xml,
+            "   <assemble_item object_id=\"{}\" instance_id=\"0\" transform=\"1 0 0 0 1 0 0 0 1 {} {} {}\" offset=\"0 0 0\" />",
+            build_ids[idx],
+            x,
+            y,
+            z
+        );
+    }
+    xml.push_str("  </assemble>\n");
+    xml.push_str("</config>\n");
+    xml
+}
+
+fn write_object_head(xml: &mut String, id: u32, name: &str) {
+    let _ = writeln!(xml, "  <object id=\"{}\">", id);
+    let _ = writeln!(
+        xml,
+        "    <metadata key=\"name\" value=\"{}\"/>",
+        xml_escape(name)
+    );
+    xml.push_str("    <metadata key=\"extruder\" value=\"1\"/>\n");
+}
+
+/// `id` is the model object holding the part's mesh.
+fn write_part(xml: &mut String, id: u32, name: &str) {
+    let _ = writeln!(xml, "    <part id=\"{}\" subtype=\"normal_part\">", id);
+    let _ = writeln!(
+        xml,
+        "      <metadata key=\"name\" value=\"{}\"/>",
+        xml_escape(name)
+    );
+    let _ = writeln!(
+        xml,
+        "      <metadata key=\"matrix\" value=\"{}\"/>",
+        IDENTITY_MATRIX
+    );
+    xml.push_str("    </part>\n");
+}
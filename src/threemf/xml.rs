@@ -0,0 +1,141 @@
+//! Streaming XML Output
+//!
+//! Writes elements straight to a byte sink without building strings per
+//! element. Numbers use shortest round-trip formatting (`ryu` / `itoa`), so
+//! a coordinate is as short as possible while still reading back to the
+//! exact same `f32`.
+
+use std::io::{self, Write};
+
+/// Minimal element writer with two-space indentation. Callers are trusted to
+/// balance `start`/`end`; element and attribute names are not escaped.
+pub struct XmlWriter<W: Write> {
+    out: W,
+    depth: usize,
+    floats: ryu::Buffer,
+    ints: itoa::Buffer,
+}
+
+impl<W: Write> XmlWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            depth: 0,
+            floats: ryu::Buffer::new(),
+            ints: itoa::Buffer::new(),
+        }
+    }
+
+    pub fn declaration(&mut self) -> io::Result<()> {
+        self.out
+            .write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")
+    }
+
+    /// Writes the indentation and `<name`; follow with attributes and
+    /// [`XmlWriter::open`] or [`XmlWriter::close_empty`].
+    pub fn tag(&mut self, name: &str) -> io::Result<()> {
+        for _ in 0..self.depth {
+            self.out.write_all(b"  ")?;
+        }
+        self.out.write_all(b"<")?;
+        self.out.write_all(name.as_bytes())
+    }
+
+    /// Writes an escaped text attribute.
+    pub fn attr(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.attr_name(name)?;
+        let mut rest = value.as_bytes();
+        while let Some(pos) = rest.iter().position(|b| b"&<>\"'".contains(b)) {
+            self.out.write_all(&rest[..pos])?;
+            let entity: &[u8] = match rest[pos] {
+                b'&' => b"&amp;",
+                b'<' => b"&lt;",
+                b'>' => b"&gt;",
+                b'"' => b"&quot;",
+                _ => b"&apos;",
+            };
+            self.out.write_all(entity)?;
+            rest = &rest[pos + 1..];
+        }
+        self.out.write_all(rest)?;
+        self.out.write_all(b"\"")
+    }
+
+    /// Writes a float attribute in shortest round-trip form (`1`, `0.1`, `1e-7`).
+    pub fn attr_f32(&mut self, name: &str, value: f32) -> io::Result<()> {
+        if !value.is_finite() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("non-finite value for attribute {}", name),
+            ));
+        }
+        self.attr_name(name)?;
+        let text = self.floats.format_finite(value);
+        let text = text.strip_suffix(".0").unwrap_or(text);
+        self.out.write_all(text.as_bytes())?;
+        self.out.write_all(b"\"")
+    }
+
+    /// Writes space-separated floats, e.g. a 3MF transform matrix.
+    pub fn attr_f32_list(&mut self, name: &str, values: &[f32]) -> io::Result<()> {
+        if let Some(bad) = values.iter().find(|v| !v.is_finite()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("non-finite value {} for attribute {}", bad, name),
+            ));
+        }
+        self.attr_name(name)?;
+        for (idx, value) in values.iter().enumerate() {
+            if idx > 0 {
+                self.out.write_all(b" ")?;
+            }
+            let text = self.floats.format_finite(*value);
+            let text = text.strip_suffix(".0").unwrap_or(text);
+            self.out.write_all(text.as_bytes())?;
+        }
+        self.out.write_all(b"\"")
+    }
+
+    pub fn attr_u32(&mut self, name: &str, value: u32) -> io::Result<()> {
+        self.attr_name(name)?;
+        self.out.write_all(self.ints.format(value).as_bytes())?;
+        self.out.write_all(b"\"")
+    }
+
+    fn attr_name(&mut self, name: &str) -> io::Result<()> {
+        self.out.write_all(b" ")?;
+        self.out.write_all(name.as_bytes())?;
+        self.out.write_all(b"=\"")
+    }
+
+    /// Ends a start tag (`>`); children follow one level deeper.
+    pub fn open(&mut self) -> io::Result<()> {
+        self.depth += 1;
+        self.out.write_all(b">\n")
+    }
+
+    /// Ends a childless element (` />`).
+    pub fn close_empty(&mut self) -> io::Result<()> {
+        self.out.write_all(b" />\n")
+    }
+
+    /// `tag` + `open` for elements without attributes.
+    pub fn start(&mut self, name: &str) -> io::Result<()> {
+        self.tag(name)?;
+        self.open()
+    }
+
+    pub fn end(&mut self, name: &str) -> io::Result<()> {
+        self.depth = self.depth.saturating_sub(1);
+        for _ in 0..self.depth {
+            self.out.write_all(b"  ")?;
+        }
+        self.out.write_all(b"</")?;
+        self.out.write_all(name.as_bytes())?;
+        self.out.write_all(b">\n")
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
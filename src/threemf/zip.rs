@@ -0,0 +1,454 @@
+//! Minimal ZIP Writer (Store / Deflate, ZIP64)
+//!
+//! Entries are Deflate-compressed at a configurable level (level 0 stores
+//! them). 3MF model XML typically shrinks 5-10x, which matters more for
+//! network transfer to Manyfold than the CPU spent compressing.
+//!
+//! Small entries are written in one call ([`ZipWriter::add_file`]) with their
+//! sizes in the local header. Large documents are streamed
+//! ([`ZipWriter::start_file`]) as data-descriptor entries: CRC and sizes follow
+//! the data, so nothing is buffered and the output never needs to seek. ZIP64
+//! records are emitted only where a size, offset or the entry count exceeds
+//! the classic 32/16-bit fields, so small packages stay readable by any ZIP
+//! tool.
+//!
+//! Every entry carries the package creation time, both as a DOS date/time
+//! and as an extended (Unix) timestamp, so file browsers and Manyfold show a
+//! real date instead of 1980-01-01.
+
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LOCAL_HEADER_SIG: u32 = 0x04034b50;
+const DATA_DESCRIPTOR_SIG: u32 = 0x08074b50;
+const CENTRAL_HEADER_SIG: u32 = 0x02014b50;
+const ZIP64_EOCD_SIG: u32 = 0x06064b50;
+const ZIP64_LOCATOR_SIG: u32 = 0x07064b50;
+const EOCD_SIG: u32 = 0x06054b50;
+
+/// Header id of the ZIP64 extended information extra field.
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+/// Header id of the Info-ZIP extended timestamp extra field.
+const TIMESTAMP_EXTRA_ID: u16 = 0x5455;
+const METHOD_STORE: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+const VERSION_STORE: u16 = 10;
+const VERSION_DEFLATE: u16 = 20;
+const VERSION_ZIP64: u16 = 45;
+/// General purpose flag bit 3: CRC and sizes are in a trailing data descriptor.
+const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+
+/// Marker stored in 32-bit fields whose real value lives in the ZIP64 extra.
+const U32_MARKER: u32 = u32::MAX;
+const U16_MARKER: u16 = u16::MAX;
+
+struct ZipEntry {
+    name: String,
+    method: u16,
+    flags: u16,
+    crc32: u32,
+    compressed: u64,
+    uncompressed: u64,
+    offset: u64,
+}
+
+impl ZipEntry {
+    fn needs_zip64(&self) -> bool {
+        self.compressed >= U32_MARKER as u64
+            || self.uncompressed >= U32_MARKER as u64
+            || self.offset >= U32_MARKER as u64
+    }
+
+    fn version_needed(&self, zip64: bool) -> u16 {
+        if zip64 {
+            VERSION_ZIP64
+        } else if self.method == METHOD_STORE {
+            VERSION_STORE
+        } else {
+            VERSION_DEFLATE
+        }
+    }
+}
+
+pub struct ZipWriter<W: Write> {
+    writer: CountingWriter<W>,
+    entries: Vec<ZipEntry>,
+    /// Deflate level 0-9; 0 stores entries uncompressed.
+    level: u32,
+    /// Modification time stamped on every entry.
+    timestamp: Timestamp,
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub fn new(writer: W, level: u32) -> Self {
+        Self {
+            writer: CountingWriter {
+                inner: writer,
+                count: 0,
+            },
+            entries: Vec::new(),
+            level: level.min(9),
+            timestamp: Timestamp::new(SystemTime::now()),
+        }
+    }
+
+    fn method(&self) -> u16 {
+        if self.level == 0 {
+            METHOD_STORE
+        } else {
+            METHOD_DEFLATE
+        }
+    }
+
+    /// Writes a complete in-memory entry with CRC and sizes in its header.
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let method = self.method();
+        let compressed = if method == METHOD_STORE {
+            None
+        } else {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::new(self.level));
+            encoder.write_all(data)?;
+            Some(encoder.finish()?)
+        };
+        let payload = compressed.as_deref().unwrap_or(data);
+
+        let entry = ZipEntry {
+            name: name.to_string(),
+            method,
+            flags: 0,
+            crc32: crc32fast::hash(data),
+            compressed: payload.len() as u64,
+            uncompressed: data.len() as u64,
+            offset: self.writer.count,
+        };
+        let large =
+            entry.compressed >= U32_MARKER as u64 || entry.uncompressed >= U32_MARKER as u64;
+        self.write_local_header(&entry, large)?;
+        self.writer.write_all(payload)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Starts a streamed entry. Data written to the returned writer is
+    /// compressed straight into the archive; call [`EntryWriter::finish`]
+    /// to append the data descriptor.
+    ///
+    /// `large` announces ZIP64 sizes in the local header and descriptor. It
+    /// must be set for entries that may reach 4 GiB, because the header is
+    /// written before the size is known.
+    pub fn start_file(&mut self, name: &str, large: bool) -> io::Result<EntryWriter<'_, W>> {
+        let method = self.method();
+        let entry = ZipEntry {
+            name: name.to_string(),
+            method,
+            flags: FLAG_DATA_DESCRIPTOR,
+            crc32: 0,
+            compressed: 0,
+            uncompressed: 0,
+            offset: self.writer.count,
+        };
+        self.write_local_header(&entry, large)?;
+
+        let start = self.writer.count;
+        let sink = if method == METHOD_STORE {
+            Sink::Store(&mut self.writer)
+        } else {
+            Sink::Deflate(DeflateEncoder::new(
+                &mut self.writer,
+                Compression::new(self.level),
+            ))
+        };
+        Ok(EntryWriter {
+            sink,
+            entries: &mut self.entries,
+            entry,
+            start,
+            large,
+            crc: crc32fast::Hasher::new(),
+        })
+    }
+
+    /// Local file header. With the data-descriptor flag the CRC and sizes are
+    /// left zero; `large` adds a ZIP64 extra field carrying the real (or, for
+    /// streamed entries, zero) 64-bit sizes.
+    fn write_local_header(&mut self, entry: &ZipEntry, large: bool) -> io::Result<()> {
+        let streamed = entry.flags & FLAG_DATA_DESCRIPTOR != 0;
+        let (crc, compressed, uncompressed) = match (streamed, large) {
+            (_, true) => (entry.crc32, U32_MARKER, U32_MARKER),
+            (true, false) => (0, 0, 0),
+            (false, false) => (
+                entry.crc32,
+                entry.compressed as u32,
+                entry.uncompressed as u32,
+            ),
+        };
+        let extra_len: u16 = if large { 20 + 9 } else { 9 };
+
+        let w = &mut self.writer;
+        w.write_all(&LOCAL_HEADER_SIG.to_le_bytes())?;
+        w.write_all(&entry.version_needed(large).to_le_bytes())?;
+        w.write_all(&entry.flags.to_le_bytes())?;
+        w.write_all(&entry.method.to_le_bytes())?;
+        w.write_all(&self.timestamp.dos_time.to_le_bytes())?;
+        w.write_all(&self.timestamp.dos_date.to_le_bytes())?;
+        w.write_all(&crc.to_le_bytes())?;
+        w.write_all(&compressed.to_le_bytes())?;
+        w.write_all(&uncompressed.to_le_bytes())?;
+        w.write_all(&(entry.name.len() as u16).to_le_bytes())?;
+        w.write_all(&extra_len.to_le_bytes())?;
+        w.write_all(entry.name.as_bytes())?;
+        if large {
+            w.write_all(&ZIP64_EXTRA_ID.to_le_bytes())?;
+            w.write_all(&16u16.to_le_bytes())?;
+            w.write_all(&entry.uncompressed.to_le_bytes())?;
+            w.write_all(&entry.compressed.to_le_bytes())?;
+        }
+        self.timestamp.write_extra(w)
+    }
+
+    /// Writes the central directory and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let dir_start = self.writer.count;
+
+        let w = &mut self.writer;
+        for entry in &self.entries {
+            let zip64 = entry.needs_zip64();
+            let mut extra = Vec::new();
+            if zip64 {
+                // All three values are always present, in spec order, with the
+                // 32-bit fields set to the marker.
+                extra.extend_from_slice(&ZIP64_EXTRA_ID.to_le_bytes());
+                extra.extend_from_slice(&24u16.to_le_bytes());
+                extra.extend_from_slice(&entry.uncompressed.to_le_bytes());
+                extra.extend_from_slice(&entry.compressed.to_le_bytes());
+                extra.extend_from_slice(&entry.offset.to_le_bytes());
+            }
+            self.timestamp.write_extra(&mut extra)?;
+            let clamp = |value: u64| if zip64 { U32_MARKER } else { value as u32 };
+
+            w.write_all(&CENTRAL_HEADER_SIG.to_le_bytes())?;
+            w.write_all(&VERSION_ZIP64.to_le_bytes())?; // Version made by
+            w.write_all(&entry.version_needed(zip64).to_le_bytes())?;
+            w.write_all(&entry.flags.to_le_bytes())?;
+            w.write_all(&entry.method.to_le_bytes())?;
+            w.write_all(&self.timestamp.dos_time.to_le_bytes())?;
+            w.write_all(&self.timestamp.dos_date.to_le_bytes())?;
+            w.write_all(&entry.crc32.to_le_bytes())?;
+            w.write_all(&clamp(entry.compressed).to_le_bytes())?;
+            w.write_all(&clamp(entry.uncompressed).to_le_bytes())?;
+            w.write_all(&(entry.name.len() as u16).to_le_bytes())?;
+            w.write_all(&(extra.len() as u16).to_le_bytes())?;
+            w.write_all(&0u16.to_le_bytes())?; // Comment len
+            w.write_all(&0u16.to_le_bytes())?; // Disk start
+            w.write_all(&0u16.to_le_bytes())?; // Internal attrs
+            w.write_all(&0u32.to_le_bytes())?; // External attrs
+            w.write_all(&clamp(entry.offset).to_le_bytes())?;
+            w.write_all(entry.name.as_bytes())?;
+            w.write_all(&extra)?;
+        }
+
+        let dir_end = w.count;
+        let dir_size = dir_end - dir_start;
+        let count = self.entries.len() as u64;
+        let zip64 = count >= U16_MARKER as u64
+            || dir_start >= U32_MARKER as u64
+            || dir_size >= U32_MARKER as u64;
+
+        if zip64 {
+            w.write_all(&ZIP64_EOCD_SIG.to_le_bytes())?;
+            w.write_all(&44u64.to_le_bytes())?; // Size of the remaining record
+            w.write_all(&VERSION_ZIP64.to_le_bytes())?; // Version made by
+            w.write_all(&VERSION_ZIP64.to_le_bytes())?; // Version needed
+            w.write_all(&0u32.to_le_bytes())?; // Disk number
+            w.write_all(&0u32.to_le_bytes())?; // Disk with CD
+            w.write_all(&count.to_le_bytes())?; // Entries on disk
+            w.write_all(&count.to_le_bytes())?; // Total entries
+            w.write_all(&dir_size.to_le_bytes())?;
+            w.write_all(&dir_start.to_le_bytes())?;
+
+            w.write_all(&ZIP64_LOCATOR_SIG.to_le_bytes())?;
+            w.write_all(&0u32.to_le_bytes())?; // Disk with ZIP64 EOCD
+            w.write_all(&dir_end.to_le_bytes())?; // ZIP64 EOCD offset
+            w.write_all(&1u32.to_le_bytes())?; // Total disks
+        }
+
+        let count16 = if zip64 { U16_MARKER } else { count as u16 };
+        let (size32, start32) = if zip64 {
+            (U32_MARKER, U32_MARKER)
+        } else {
+            (dir_size as u32, dir_start as u32)
+        };
+        w.write_all(&EOCD_SIG.to_le_bytes())?;
+        w.write_all(&0u16.to_le_bytes())?; // Disk number
+        w.write_all(&0u16.to_le_bytes())?; // Disk with CD
+        w.write_all(&count16.to_le_bytes())?; // Entries on disk
+        w.write_all(&count16.to_le_bytes())?; // Total entries
+        w.write_all(&size32.to_le_bytes())?;
+        w.write_all(&start32.to_le_bytes())?;
+        w.write_all(&0u16.to_le_bytes())?; // Comment len
+
+        w.flush()?;
+        Ok(self.writer.inner)
+    }
+}
+
+/// Tracks the archive offset without requiring `Seek`.
+struct CountingWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+enum Sink<'a, W: Write> {
+    Store(&'a mut CountingWriter<W>),
+    Deflate(DeflateEncoder<&'a mut CountingWriter<W>>),
+}
+
+/// Streaming writer for a single entry.
+pub struct EntryWriter<'a, W: Write> {
+    sink: Sink<'a, W>,
+    entries: &'a mut Vec<ZipEntry>,
+    entry: ZipEntry,
+    /// Archive offset where the entry data begins.
+    start: u64,
+    large: bool,
+    crc: crc32fast::Hasher,
+}
+
+impl<W: Write> EntryWriter<'_, W> {
+    /// Flushes the compressor, writes the data descriptor and registers the
+    /// entry.
+    pub fn finish(self) -> io::Result<()> {
+        let EntryWriter {
+            sink,
+            entries,
+            mut entry,
+            start,
+            large,
+            crc,
+        } = self;
+        let w = match sink {
+            Sink::Store(w) => w,
+            Sink::Deflate(encoder) => encoder.finish()?,
+        };
+        entry.crc32 = crc.finalize();
+        entry.compressed = w.count - start;
+
+        w.write_all(&DATA_DESCRIPTOR_SIG.to_le_bytes())?;
+        w.write_all(&entry.crc32.to_le_bytes())?;
+        if large {
+            w.write_all(&entry.compressed.to_le_bytes())?;
+            w.write_all(&entry.uncompressed.to_le_bytes())?;
+        } else if entry.compressed < U32_MARKER as u64 && entry.uncompressed < U32_MARKER as u64 {
+            w.write_all(&(entry.compressed as u32).to_le_bytes())?;
+            w.write_all(&(entry.uncompressed as u32).to_le_bytes())?;
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} exceeds 4 GiB but was not started as a large entry",
+                    entry.name
+                ),
+            ));
+        }
+
+        entries.push(entry);
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for EntryWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = match &mut self.sink {
+            Sink::Store(w) => w.write(buf)?,
+            Sink::Deflate(encoder) => encoder.write(buf)?,
+        };
+        self.crc.update(&buf[..n]);
+        self.entry.uncompressed += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            Sink::Store(w) => w.flush(),
+            Sink::Deflate(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Entry modification time in both encodings ZIP readers understand.
+///
+/// The DOS fields are filled in UTC on purpose: they have no time zone, the
+/// container usually runs without one, and a library shared between machines
+/// has no single "local" time anyway. Readers that honour the extended
+/// timestamp extra field (0x5455) take the exact time from there instead.
+struct Timestamp {
+    dos_time: u16,
+    dos_date: u16,
+    unix: u32,
+}
+
+impl Timestamp {
+    fn new(time: SystemTime) -> Self {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        let rem = secs % 86_400;
+        let (hour, minute, second) = (rem / 3600, rem % 3600 / 60, rem % 60);
+
+        // DOS dates cover 1980-2107 in 2-second steps; clamp rather than wrap.
+        let (dos_time, dos_date) = if year < 1980 {
+            (0, (1 << 5) | 1)
+        } else if year > 2107 {
+            ((23 << 11) | (59 << 5) | 29, (127 << 9) | (12 << 5) | 31)
+        } else {
+            (
+                ((hour << 11) | (minute << 5) | (second / 2)) as u16,
+                (((year - 1980) << 9) as u32 | (month << 5) | day) as u16,
+            )
+        };
+        Self {
+            dos_time,
+            dos_date,
+            unix: secs.min(u32::MAX as u64) as u32,
+        }
+    }
+
+    /// Writes the extended timestamp extra field (modification time only).
+    fn write_extra<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&TIMESTAMP_EXTRA_ID.to_le_bytes())?;
+        w.write_all(&5u16.to_le_bytes())?;
+        w.write_all(&[0x01])?; // Flags: mtime present
+        w.write_all(&self.unix.to_le_bytes())
+    }
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) UTC date
+/// (Howard Hinnant's `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,257 @@
+//! Input Folder Watcher (File Monitor)
+//!
+//! Governance: .agent/skills/architectural_guidelines/SKILL.md (Level 3: File Monitor)
+//!
+//! Observes the intake volume recursively and emits one path per *top-level*
+//! item (file or project folder) once it has settled. An item is settled when
+//! no events arrived for `settle_delay` and two consecutive size snapshots are
+//! identical, which covers slow SMB/rsync copies that keep growing for minutes.
+//!
+//! Loose images at the top level are not items of their own: they are the
+//! preview candidates of loose model files next to them.
+
+use crate::config::Config;
+use crate::project::IMAGE_EXTENSIONS;
+use crate::queue::JobQueue;
+use notify::{Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+
+/// Size fingerprint of a file or directory tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Snapshot {
+    bytes: u64,
+    files: u64,
+    newest: Option<SystemTime>,
+}
+
+/// Debounce state for one top-level item.
+struct PendingItem {
+    last_event: Instant,
+    last_snapshot: Option<Snapshot>,
+}
+
+/// Holds the underlying `notify` watcher; dropping it stops event delivery.
+pub struct InputWatcher {
+    _watcher: Box<dyn Watcher + Send>,
+}
+
+/// Starts watching `config.input_dir` and enqueues settled top-level items.
+///
+/// Existing items are picked up by a startup scan so that files dropped while the
+/// service was down are not missed.
+pub fn start(config: &Config, queue: Arc<JobQueue>) -> anyhow::Result<InputWatcher> {
+    let root = config.input_dir.clone();
+    if !root.exists() {
+        log::warn!(
+            "Watcher: input directory {:?} does not exist, creating it",
+            root
+        );
+        std::fs::create_dir_all(&root)?;
+    }
+
+    let (event_tx, event_rx) = mpsc::unbounded_channel::<PathBuf>();
+    let handler = move |res: notify::Result<Event>| match res {
+        Ok(event) => {
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            for path in event.paths {
+                let _ = event_tx.send(path);
+            }
+        }
+        Err(e) => log::warn!("Watcher: notify error: {}", e),
+    };
+
+    let mut watcher: Box<dyn Watcher + Send> = if config.watcher_polling {
+        log::info!(
+            "Watcher: using polling backend ({:?})",
+            config.poll_interval
+        );
+        let notify_config = notify::Config::default().with_poll_interval(config.poll_interval);
+        Box::new(PollWatcher::new(handler, notify_config)?)
+    } else {
+        Box::new(RecommendedWatcher::new(handler, notify::Config::default())?)
+    };
+    watcher.watch(&root, RecursiveMode::Recursive)?;
+
+    let startup_items = scan_top_level(&root);
+    log::info!(
+        "Watcher: observing {:?} (recursive, settle {:?}, {} existing item(s))",
+        root,
+        config.settle_delay,
+        startup_items.len()
+    );
+
+    tokio::spawn(debounce_loop(
+        root,
+        config.settle_delay,
+        config.poll_interval,
+        startup_items,
+        event_rx,
+        queue,
+    ));
+
+    Ok(InputWatcher { _watcher: watcher })
+}
+
+async fn debounce_loop(
+    root: PathBuf,
+    settle_delay: Duration,
+    poll_interval: Duration,
+    startup_items: Vec<PathBuf>,
+    mut events: mpsc::UnboundedReceiver<PathBuf>,
+    queue: Arc<JobQueue>,
+) {
+    let mut pending: HashMap<PathBuf, PendingItem> = HashMap::new();
+    for item in startup_items {
+        pending.insert(
+            item,
+            PendingItem {
+                last_event: Instant::now(),
+                last_snapshot: None,
+            },
+        );
+    }
+
+    let mut ticker = tokio::time::interval(poll_interval);
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let Some(path) = event else { break };
+                if let Some(item) = top_level_item(&root, &path) {
+                    let entry = pending.entry(item.clone()).or_insert_with(|| {
+                        log::debug!("Watcher: activity on {:?}, debouncing", item);
+                        PendingItem { last_event: Instant::now(), last_snapshot: None }
+                    });
+                    entry.last_event = Instant::now();
+                }
+            }
+            _ = ticker.tick() => {
+                let due: Vec<PathBuf> = pending
+                    .iter()
+                    .filter(|(_, item)| item.last_event.elapsed() >= settle_delay)
+                    .map(|(path, _)| path.clone())
+                    .collect();
+
+                for path in due {
+                    let probe = path.clone();
+                    let snapshot = tokio::task::spawn_blocking(move || snapshot(&probe))
+                        .await
+                        .ok()
+                        .flatten();
+                    let Some(snapshot) = snapshot else {
+                        log::debug!("Watcher: {:?} vanished before settling", path);
+                        pending.remove(&path);
+                        continue;
+                    };
+
+                    let Some(item) = pending.get_mut(&path) else { continue };
+                    if item.last_snapshot == Some(snapshot) {
+                        pending.remove(&path);
+                        log::info!(
+                            "Watcher: {:?} settled ({} file(s), {} bytes), enqueueing",
+                            path,
+                            snapshot.files,
+                            snapshot.bytes
+                        );
+                        queue.enqueue(path);
+                    } else {
+                        // Still growing (or first check): wait another poll interval.
+                        item.last_snapshot = Some(snapshot);
+                    }
+                }
+            }
+        }
+    }
+    log::warn!("Watcher: event channel closed, debounce loop exiting");
+}
+
+/// Maps any path below `root` to its top-level entry, skipping hidden and
+/// partial-download names (rsync temp files, browser `.part`/`.crdownload`)
+/// and loose images.
+fn top_level_item(root: &Path, path: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(root).ok()?;
+    let first = relative.components().next()?;
+    let name = first.as_os_str().to_string_lossy();
+    if is_ignored_name(&name) {
+        return None;
+    }
+    if relative.components().count() == 1 && (is_partial_download(&name) || is_loose_image(path)) {
+        return None;
+    }
+    Some(root.join(first))
+}
+
+fn is_ignored_name(name: &str) -> bool {
+    name.starts_with('.') || name.starts_with("~$")
+}
+
+fn is_loose_image(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    IMAGE_EXTENSIONS.contains(&ext.as_str()) && !path.is_dir()
+}
+
+fn is_partial_download(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    [".part", ".partial", ".crdownload", ".tmp", ".download"]
+        .iter()
+        .any(|suffix| lower.ends_with(suffix))
+}
+
+/// Lists the top-level items currently present in the input directory.
+pub fn scan_top_level(root: &Path) -> Vec<PathBuf> {
+    match std::fs::read_dir(root) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| top_level_item(root, &entry.path()))
+            .collect(),
+        Err(e) => {
+            log::error!("Watcher: startup scan of {:?} failed: {}", root, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Newest modification time anywhere in the tree at `path`.
+pub fn newest_modified(path: &Path) -> Option<SystemTime> {
+    snapshot(path)?.newest
+}
+
+/// Returns `None` if the item no longer exists.
+fn snapshot(path: &Path) -> Option<Snapshot> {
+    let meta = std::fs::symlink_metadata(path).ok()?;
+    let mut snap = Snapshot {
+        bytes: 0,
+        files: 0,
+        newest: None,
+    };
+    accumulate(path, &meta, &mut snap);
+    Some(snap)
+}
+
+fn accumulate(path: &Path, meta: &std::fs::Metadata, snap: &mut Snapshot) {
+    let modified = meta.modified().ok();
+    if modified > snap.newest {
+        snap.newest = modified;
+    }
+    if meta.is_dir() {
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if let Ok(child_meta) = entry.metadata() {
+                accumulate(&entry.path(), &child_meta, snap);
+            }
+        }
+    } else {
+        snap.bytes += meta.len();
+        snap.files += 1;
+    }
+}
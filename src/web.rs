@@ -1,19 +1,101 @@
+use crate::progress::JobEvent;
+use crate::queue::{Job, JobQueue, JobState, QueueError};
+use crate::threemf::{self, PackageInfo};
+use crate::watcher;
 use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     routing::{get, post},
     Json, Router,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
 use tower_http::services::ServeDir;
 
+/// Shared state handed to every request handler.
+#[derive(Clone)]
+pub struct AppState {
+    pub queue: Arc<JobQueue>,
+    pub input_dir: PathBuf,
+}
+
 #[derive(Serialize)]
 struct Status {
     engine_status: String,
     queue_count: usize,
     processed_count: usize,
+    failed_count: usize,
+}
+
+/// Compact job representation for list responses (no log).
+#[derive(Serialize)]
+struct JobSummary {
+    id: u64,
+    source: PathBuf,
+    state: JobState,
+    attempts: u32,
+    created_at: u64,
+    updated_at: u64,
+    error: Option<String>,
+    failure_reason: Option<String>,
+    artifact_count: usize,
 }
 
-pub async fn start_web_server() -> anyhow::Result<()> {
+impl From<Job> for JobSummary {
+    fn from(job: Job) -> Self {
+        Self {
+            id: job.id,
+            source: job.source,
+            state: job.state,
+            attempts: job.attempts,
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+            error: job.error,
+            failure_reason: job.failure_reason,
+            artifact_count: job.artifacts.len(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JobFilter {
+    state: Option<JobState>,
+}
+
+#[derive(Deserialize)]
+struct EventFilter {
+    job: Option<u64>,
+}
+
+/// JSON error body with a matching HTTP status.
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "status": "error", "message": self.1 }));
+        (self.0, body).into_response()
+    }
+}
+
+impl From<QueueError> for ApiError {
+    fn from(e: QueueError) -> Self {
+        let code = match e {
+            QueueError::NotFound(_) => StatusCode::NOT_FOUND,
+            QueueError::InvalidState { .. } => StatusCode::CONFLICT,
+        };
+        ApiError(code, e.to_string())
+    }
+}
+
+pub async fn start_web_server(state: AppState, port: u16) -> anyhow::Result<()> {
     // Serve static files from the "static" directory
     let static_files = ServeDir::new("static");
 
@@ -21,10 +103,17 @@ pub async fn start_web_server() -> anyhow::Result<()> {
     let app = Router::new()
         .nest_service("/", static_files)
         .route("/api/status", get(get_status))
-        .route("/api/process/all", post(process_all));
+        .route("/api/process/all", post(process_all))
+        .route("/api/jobs", get(list_jobs).delete(delete_finished_jobs))
+        .route("/api/jobs/:id", get(get_job).delete(delete_job))
+        .route("/api/jobs/:id/retry", post(retry_job))
+        .route("/api/jobs/:id/cancel", post(cancel_job))
+        .route("/api/jobs/:id/package", get(get_job_package))
+        .route("/api/events", get(job_events))
+        .with_state(state);
 
     // Define the address
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
     log::info!("Web server listening on http://{}", addr);
 
     // Start the server
@@ -36,15 +125,138 @@ pub async fn start_web_server() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn get_status() -> Json<Status> {
+async fn get_status(State(state): State<AppState>) -> Json<Status> {
+    let counts = state.queue.counts();
     Json(Status {
         engine_status: "online".to_string(),
-        queue_count: 12,     // Placeholder
-        processed_count: 88, // Placeholder
+        queue_count: counts.pending + counts.running,
+        processed_count: counts.succeeded,
+        failed_count: counts.failed,
     })
 }
 
-async fn process_all() -> Json<serde_json::Value> {
+async fn list_jobs(
+    State(state): State<AppState>,
+    Query(filter): Query<JobFilter>,
+) -> Json<Vec<JobSummary>> {
+    let jobs = state.queue.list(filter.state);
+    Json(jobs.into_iter().map(JobSummary::from).collect())
+}
+
+async fn get_job(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Json<Job>, ApiError> {
+    state
+        .queue
+        .get(id)
+        .map(Json)
+        .ok_or_else(|| QueueError::NotFound(id).into())
+}
+
+async fn retry_job(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Json<Job>, ApiError> {
+    log::info!("API: retry requested for job {}", id);
+    Ok(Json(state.queue.retry(id)?))
+}
+
+async fn cancel_job(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Json<Job>, ApiError> {
+    log::info!("API: cancel requested for job {}", id);
+    Ok(Json(state.queue.cancel(id)?))
+}
+
+/// Metadata, counts and thumbnails of the 3MF a job produced.
+async fn get_job_package(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Json<PackageInfo>, ApiError> {
+    let job = state.queue.get(id).ok_or(QueueError::NotFound(id))?;
+    let package = job
+        .artifacts
+        .iter()
+        .find(|a| a.extension().is_some_and(|e| e.eq_ignore_ascii_case("3mf")))
+        .cloned()
+        .ok_or_else(|| {
+            ApiError(
+                StatusCode::NOT_FOUND,
+                format!("Job {} has no 3MF artifact", id),
+            )
+        })?;
+    tokio::task::spawn_blocking(move || threemf::read_package(&package))
+        .await
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .map_err(|e| ApiError(StatusCode::UNPROCESSABLE_ENTITY, format!("{:#}", e)))
+}
+
+async fn delete_job(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.queue.remove(id)?;
+    Ok(Json(
+        serde_json::json!({ "status": "success", "deleted": 1 }),
+    ))
+}
+
+async fn delete_finished_jobs(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let deleted = state.queue.remove_finished();
+    log::info!("API: removed {} finished job(s)", deleted);
+    Json(serde_json::json!({ "status": "success", "deleted": deleted }))
+}
+
+async fn process_all(State(state): State<AppState>) -> Json<serde_json::Value> {
     log::info!("Triggering manual process-all from UI");
-    Json(serde_json::json!({ "status": "success", "message": "Batch processing initiated" }))
+    let items = watcher::scan_top_level(&state.input_dir);
+    let enqueued = items
+        .into_iter()
+        .filter_map(|item| state.queue.enqueue(item))
+        .count();
+    Json(serde_json::json!({
+        "status": "success",
+        "message": format!("Batch processing initiated ({} new job(s))", enqueued),
+        "enqueued": enqueued,
+    }))
+}
+
+/// Server-Sent Events stream of job progress and state changes.
+///
+/// Each message is a JSON [`JobEvent`]; the SSE event name is its `type`
+/// (`progress` or `state`). `?job=<id>` limits the stream to one job.
+async fn job_events(
+    State(state): State<AppState>,
+    Query(filter): Query<EventFilter>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (snapshot, rx) = state.queue.events().subscribe();
+    let live = BroadcastStream::new(rx).filter_map(|event| match event {
+        Ok(event) => Some(event),
+        Err(e) => {
+            // Slow client: the dashboard resynchronises from /api/jobs on the next state event.
+            log::debug!("SSE subscriber lagging: {}", e);
+            None
+        }
+    });
+
+    let stream = tokio_stream::iter(snapshot)
+        .chain(live)
+        .filter(move |event| filter.job.is_none_or(|id| event.job_id() == id))
+        .map(|event| Ok(to_sse(&event)));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn to_sse(event: &JobEvent) -> Event {
+    let name = match event {
+        JobEvent::Progress { .. } => "progress",
+        JobEvent::State { .. } => "state",
+    };
+    Event::default()
+        .event(name)
+        .json_data(event)
+        .unwrap_or_else(|_| Event::default().event(name))
 }
@@ -0,0 +1,56 @@
+Feature: Archive Intake
+  As a maker
+  I want downloaded archives unpacked and processed like folders
+  So that I do not have to extract every model pack by hand.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Architecture: architectural_guidelines]
+
+  Scenario Outline: A <format> archive is unpacked into a model
+    Given an archive "<archive>" with the files:
+      | file       |
+      | kit.stl    |
+      | readme.txt |
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "<archive>" into the input folder
+    Then the library contains "kit/kit.3mf"  # [twin: Then_API the job for "<archive>" succeeds]
+    And the library contains "kit/readme.txt"
+    Then_API the job for "<archive>" succeeds
+    And_API the job log for "<archive>" mentions "Extracted <format> archive"
+
+    Examples:
+      | archive     | format       |
+      | kit.zip     | ZIP          |
+      | kit.tar     | TAR          |
+      | kit.tar.gz  | TAR (gzip)   |
+      | kit.tgz     | TAR (gzip)   |
+      | kit.tar.xz  | TAR (xz)     |
+      | kit.txz     | TAR (xz)     |
+      | kit.tar.zst | TAR (zstd)   |
+      | kit.tzst    | TAR (zstd)   |
+
+  Scenario: An archive inside an archive is unpacked too
+    Given an archive "supported.zip" with the files:
+      | file    |
+      | ork.stl |
+    And an archive "ork.tar.gz" containing the archive "supported.zip"
+    And_API the Manyfold Processor service is running
+    When I drop "ork.tar.gz" into the input folder
+    Then the library contains "ork/ork.3mf"  # [twin: Then_API the job for "ork.tar.gz" succeeds]
+    And the datapackage of "ork" records the source "ork.tar.gz > supported.zip"
+    Then_API the job for "ork.tar.gz" succeeds
+
+  Scenario: A damaged archive is rejected
+    Given a file "broken.zip" of 100 bytes
+    And_API the Manyfold Processor service is running
+    When I drop "broken.zip" into the input folder
+    Then the library does not contain "broken"  # [twin: Then_API the job for "broken.zip" fails with reason "archive_corrupt"]
+    Then_API the job for "broken.zip" fails with reason "archive_corrupt"
+
+  Scenario: A compressed file that is not a tarball is not unpacked
+    Given a file "notes.gz" of 100 bytes
+    And_API the Manyfold Processor service is running
+    When I drop "notes.gz" into the input folder
+    Then the library does not contain "notes"  # [twin: Then_API the job for "notes.gz" fails]
+    Then_API the job for "notes.gz" fails
+    And_API the job log for "notes.gz" mentions "Routed as unsupported input"
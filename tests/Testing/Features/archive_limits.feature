@@ -0,0 +1,39 @@
+Feature: Archive Size Limits
+  As an operator
+  I want archives that expand beyond the configured limits stopped
+  So that a zip bomb cannot fill the working disk or stall the queue.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Architecture: architectural_guidelines]
+
+  Scenario: An archive with too many entries is rejected
+    Given the processor setting "ARCHIVE_MAX_ENTRIES" is "10"
+    And an archive "crowd.zip" with 11 files
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "crowd.zip" into the input folder
+    Then the library does not contain "crowd"  # [twin: Then_API the job for "crowd.zip" fails with reason "archive_too_many_entries"]
+    Then_API the job for "crowd.zip" fails with reason "archive_too_many_entries"
+
+  Scenario: An archive at the entry limit is accepted
+    Given the processor setting "ARCHIVE_MAX_ENTRIES" is "10"
+    And an archive "crowd.zip" with 10 files
+    And_API the Manyfold Processor service is running
+    When I drop "crowd.zip" into the input folder
+    Then the library contains "crowd/crowd.3mf"  # [twin: Then_API the job for "crowd.zip" succeeds]
+    Then_API the job for "crowd.zip" succeeds
+
+  Scenario: An archive expanding far beyond its size is rejected
+    Given an archive "bomb.zip" with a model and 20 MB of zeros
+    And_API the Manyfold Processor service is running
+    When I drop "bomb.zip" into the input folder
+    Then the library does not contain "bomb"  # [twin: Then_API the job for "bomb.zip" fails with reason "archive_ratio_exceeded"]
+    And no file named "padding.bin" is written
+    Then_API the job for "bomb.zip" fails with reason "archive_ratio_exceeded"
+
+  Scenario: An archive larger than the size limit is rejected
+    Given the processor setting "ARCHIVE_MAX_SIZE_MB" is "1"
+    And an archive "large.tar" with a model and 2 MB of zeros
+    And_API the Manyfold Processor service is running
+    When I drop "large.tar" into the input folder
+    Then the library does not contain "large"  # [twin: Then_API the job for "large.tar" fails with reason "archive_too_large"]
+    Then_API the job for "large.tar" fails with reason "archive_too_large"
@@ -0,0 +1,48 @@
+Feature: Archive Path Safety
+  As an operator
+  I want archive entries kept inside their extraction folder
+  So that a hostile download cannot overwrite files on the server.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Architecture: architectural_guidelines]
+
+  Scenario: An entry climbing out of the archive is rejected
+    Given an archive "escape.zip" with an entry named "../../escape.stl"
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "escape.zip" into the input folder
+    Then the library does not contain "escape"  # [twin: Then_API the job for "escape.zip" fails with reason "archive_unsafe_path"]
+    And no file named "escape.stl" is written
+    Then_API the job for "escape.zip" fails with reason "archive_unsafe_path"
+
+  Scenario Outline: An entry with an absolute path is rejected
+    Given an archive "absolute.zip" with an entry named "<entry>"
+    And_API the Manyfold Processor service is running
+    When I drop "absolute.zip" into the input folder
+    Then the library does not contain "absolute"  # [twin: Then_API the job for "absolute.zip" fails with reason "archive_unsafe_path"]
+    Then_API the job for "absolute.zip" fails with reason "archive_unsafe_path"
+
+    Examples:
+      | entry                |
+      | /tmp/absolute.stl    |
+      | C:\\temp\\absolute.stl |
+
+  Scenario Outline: A link inside a <format> archive is rejected
+    Given an archive "<archive>" with a link "model_link.stl" to "/etc/passwd"
+    And_API the Manyfold Processor service is running
+    When I drop "<archive>" into the input folder
+    Then the library does not contain "linked"  # [twin: Then_API the job for "<archive>" fails with reason "archive_link"]
+    And no file named "model_link.stl" is written
+    Then_API the job for "<archive>" fails with reason "archive_link"
+
+    Examples:
+      | archive       | format |
+      | linked.zip    | ZIP    |
+      | linked.tar.gz | TAR    |
+
+  Scenario: An entry nested deeper than allowed is rejected
+    Given the processor setting "ARCHIVE_MAX_PATH_DEPTH" is "3"
+    And an archive "deep.zip" with an entry named "a/b/c/d/deep.stl"
+    And_API the Manyfold Processor service is running
+    When I drop "deep.zip" into the input folder
+    Then the library does not contain "deep"  # [twin: Then_API the job for "deep.zip" fails with reason "archive_too_deep"]
+    Then_API the job for "deep.zip" fails with reason "archive_too_deep"
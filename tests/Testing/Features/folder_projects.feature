@@ -0,0 +1,65 @@
+Feature: Folder Projects
+  As a maker
+  I want dropped folders split into the models they hold
+  So that packs, variants and their extras arrive in Manyfold as I expect.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Architecture: architectural_guidelines]
+
+  Scenario: A pack of model folders becomes one model per folder
+    Given a folder "Heroes Pack" with the files:
+      | file              |
+      | Knight/knight.stl |
+      | Wizard/wizard.stl |
+      | README.txt        |
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "Heroes Pack" into the input folder
+    Then the library contains "knight/knight.3mf"  # [twin: Then_API the job for "Heroes Pack" succeeds]
+    And the library contains "wizard/wizard.3mf"
+    And the library contains "wizard/README.txt"
+    Then_API the job for "Heroes Pack" succeeds
+
+  Scenario: Size variants stay together as one model
+    Given a folder "Ork Boss" with the files:
+      | file         |
+      | 32mm/ork.stl |
+      | 75mm/ork.stl |
+    And_API the Manyfold Processor service is running
+    When I drop "Ork Boss" into the input folder
+    Then the library contains "ork-boss/32mm/ork-boss-32mm.3mf"  # [twin: Then_API the job for "Ork Boss" succeeds]
+    And the library contains "ork-boss/75mm/ork-boss-75mm.3mf"
+    Then_API the job for "Ork Boss" succeeds
+
+  Scenario: Operating system junk is left behind
+    Given a folder "Dragon" with the files:
+      | file                  |
+      | dragon.stl            |
+      | .DS_Store             |
+      | __MACOSX/._dragon.stl |
+    And_API the Manyfold Processor service is running
+    When I drop "Dragon" into the input folder
+    Then the library contains "dragon/dragon.3mf"  # [twin: Then_API the job for "Dragon" succeeds]
+    And the library does not contain "dragon/.DS_Store"
+    And the library does not contain "dragon/__MACOSX"
+    Then_API the job log for "Dragon" mentions "Dropped 2 junk file(s)"
+
+  Scenario: A datapackage shipped with the model is kept
+    Given a folder "Fancy Cube" with the files:
+      | file     |
+      | cube.stl |
+    And the folder "Fancy Cube" ships a datapackage titled "The Fanciest Cube"
+    And_API the Manyfold Processor service is running
+    When I drop "Fancy Cube" into the input folder
+    Then the library contains "fancy-cube/fancy-cube.3mf"  # [twin: Then_API the job for "Fancy Cube" succeeds]
+    And the datapackage of "fancy-cube" has the title "The Fanciest Cube"
+    And the datapackage of "fancy-cube" keeps the license "CC-BY-4.0"
+
+  Scenario: A folder without models is rejected
+    Given a folder "Docs" with the files:
+      | file       |
+      | manual.pdf |
+    And_API the Manyfold Processor service is running
+    When I drop "Docs" into the input folder
+    Then the library does not contain "docs"  # [twin: Then_API the job for "Docs" fails]
+    Then_API the job for "Docs" fails
+    And_API the job log for "Docs" mentions "contains no models"
@@ -0,0 +1,41 @@
+Feature: Job Management API
+  As an operator
+  I want to retry, cancel and delete jobs over the API
+  So that I can manage the queue from the dashboard or a script.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Architecture: architectural_guidelines]
+
+  Scenario: A failed job is retried
+    Given a file "tiny.stl" of 20 bytes
+    And_API the Manyfold Processor service is running
+    When I drop "tiny.stl" into the input folder
+    And_API I retry the job for "tiny.stl"
+    Then_API the response shows the job as "pending"
+    And_API the job for "tiny.stl" fails with reason "stl_too_short"
+    And_API the job log for "tiny.stl" mentions "Retry requested"
+
+  Scenario: A pending job is cancelled before it starts
+    Given a binary STL file "cube.stl"
+    And_API the Manyfold Processor service is running
+    And the processor is busy converting "big.stl"
+    When I drop "cube.stl" into the input folder
+    And_API I cancel the job for "cube.stl"
+    Then_API the response shows the job as "cancelled"
+    And_API the job log for "cube.stl" mentions "Cancelled before start"
+
+  Scenario: A running job stops at its next checkpoint
+    Given_API the Manyfold Processor service is running
+    And the processor is busy converting "big.stl"
+    When_API I cancel the job for "big.stl"
+    Then the library does not contain "big"  # [twin: Then_API the job for "big.stl" is cancelled]
+    Then_API the job for "big.stl" is cancelled
+    And_API the job log for "big.stl" mentions "Cancellation requested"
+
+  Scenario: A deleted job leaves its model in the library
+    Given a binary STL file "cube.stl"
+    And_API the Manyfold Processor service is running
+    When I drop "cube.stl" into the input folder
+    And_API I delete the job for "cube.stl"
+    Then the library contains "cube/cube.3mf"
+    Then_API the job for "cube.stl" is no longer listed
@@ -0,0 +1,16 @@
+Feature: Job Events
+  As a user of the dashboard
+  I want job progress and state changes pushed to me
+  So that I can follow a conversion without refreshing the page.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Observability: observability_standards]
+
+  Scenario: The events of one job are streamed
+    Given a binary STL file "cube.stl"
+    And a file "tiny.stl" of 20 bytes
+    And_API the Manyfold Processor service is running
+    When_API I follow the events of job 2
+    And I drop "cube.stl" and "tiny.stl" into the input folder
+    Then_API the event stream reports job 2 as "running"
+    And_API the event stream carries only events of job 2
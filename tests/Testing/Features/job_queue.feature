@@ -0,0 +1,44 @@
+Feature: Job Queue
+  As an operator
+  I want the job queue to survive restarts and report what it did
+  So that no drop is lost or processed twice when the container restarts.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Architecture: architectural_guidelines]
+
+  Scenario: A job interrupted by a restart is queued again
+    Given a binary STL file "cube.stl"
+    And the processor stopped while converting "cube.stl"
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    Then the library contains "cube/cube.3mf"  # [twin: Then_API the job for "cube.stl" succeeds on attempt 2]
+    Then_API the job for "cube.stl" succeeds on attempt 2
+
+  Scenario: A job interrupted on its last attempt fails
+    Given the processor setting "JOB_MAX_ATTEMPTS" is "1"
+    And a binary STL file "cube.stl"
+    And the processor stopped while converting "cube.stl"
+    And_API the Manyfold Processor service is running
+    Then the library does not contain "cube"  # [twin: Then_API the job for "cube.stl" fails with reason "interrupted"]
+    Then_API the job for "cube.stl" fails with reason "interrupted"
+
+  Scenario: The status counts the jobs in the queue
+    Given a binary STL file "cube.stl"
+    And a file "tiny.stl" of 20 bytes
+    And_API the Manyfold Processor service is running
+    When I drop "cube.stl" and "tiny.stl" into the input folder
+    Then_API the status reports 1 processed, 1 failed and 0 queued jobs
+
+  Scenario: A file changed inside a processed folder is processed again
+    Given a folder "Dragon" with the files:
+      | file       |
+      | dragon.stl |
+    And_API the Manyfold Processor service is running
+    When I drop "Dragon" into the input folder
+    And_API the job for "Dragon" has finished
+    And I change "Dragon/dragon.stl" in the input folder
+    Then_API 2 jobs are created for "Dragon"
+
+  Scenario: Spool files left behind by a crash are removed at startup
+    Given the processor left spool files behind
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    Then no spool files are left
@@ -0,0 +1,26 @@
+Feature: Library Publishing
+  As a maker
+  I want a dropped collection to appear in the library as a whole or not at all
+  So that Manyfold never lists half of a pack.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Architecture: architectural_guidelines]
+
+  Scenario: Models published before a failing one are taken back
+    Given a folder "Heroes Pack" with the files:
+      | file              |
+      | Knight/knight.stl |
+      | Wizard/wizard.stl |
+    And the library already holds a file named "wizard"
+    And_API the Manyfold Processor service is running
+    When I drop "Heroes Pack" into the input folder
+    Then the library does not contain "knight"  # [twin: Then_API the job for "Heroes Pack" fails]
+    Then_API the job for "Heroes Pack" fails
+    And_API the job log for "Heroes Pack" mentions "rolling back"
+
+  Scenario: A staged model leaves nothing behind once published
+    Given a binary STL file "cube.stl"
+    And_API the Manyfold Processor service is running
+    When I drop "cube.stl" into the input folder
+    Then the library contains "cube/cube.3mf"  # [twin: Then_API the job for "cube.stl" succeeds]
+    And the library does not contain ".staging/job-1"
@@ -0,0 +1,42 @@
+Feature: OBJ Intake
+  As a maker
+  I want Wavefront OBJ files to become 3MF models
+  So that models exported from modelling tools land in Manyfold like STLs do.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Governance: geometry_governance]
+
+  Scenario: Every OBJ group becomes its own object
+    Given an OBJ file "robot.obj" with the groups "head" and "body"
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "robot.obj" into the input folder
+    Then the library contains "robot/robot.3mf"  # [twin: Then_API the job for "robot.obj" succeeds]
+    And the model "robot/robot.3mf" contains an object named "head"
+    And the model "robot/robot.3mf" contains 2 objects  # [twin: Then_API the package of the job for "robot.obj" has 2 objects]
+    Then_API the package of the job for "robot.obj" has 2 objects
+
+  Scenario: A face referencing a missing vertex is rejected
+    Given an OBJ file "gap.obj" whose last face references vertex 99
+    And_API the Manyfold Processor service is running
+    When I drop "gap.obj" into the input folder
+    Then the library does not contain "gap"  # [twin: Then_API the job for "gap.obj" fails with reason "obj_bad_index"]
+    Then_API the job for "gap.obj" fails with reason "obj_bad_index"
+    And_API the job log for "gap.obj" mentions "vertex index 99 is out of range"
+
+  Scenario: A vertex that is not a finite number is rejected
+    Given an OBJ file "nan.obj" with the vertex "0 NaN 0"
+    And_API the Manyfold Processor service is running
+    When I drop "nan.obj" into the input folder
+    Then_API the job for "nan.obj" fails with reason "obj_non_finite"
+
+  Scenario Outline: A material library outside the model folder is not read
+    Given an OBJ file "peek.obj" using the material library "<library>"
+    And_API the Manyfold Processor service is running
+    When I drop "peek.obj" into the input folder
+    Then the library contains "peek/peek.3mf"  # [twin: Then_API the job for "peek.obj" succeeds]
+    Then_API the job for "peek.obj" succeeds
+
+    Examples:
+      | library            |
+      | /dev/zero          |
+      | ../../../dev/zero  |
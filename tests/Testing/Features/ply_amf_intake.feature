@@ -0,0 +1,42 @@
+Feature: PLY and AMF Intake
+  As a maker
+  I want PLY scans and AMF files to become 3MF models
+  So that every common mesh format ends up in Manyfold the same way.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Governance: geometry_governance]
+
+  Scenario Outline: A PLY file becomes a 3MF model
+    Given <kind> PLY file "<file>"
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "<file>" into the input folder
+    Then the library contains "<model>"  # [twin: Then_API the job for "<file>" succeeds]
+    Then_API the job for "<file>" succeeds
+    And_API the job log for "<file>" mentions "12 triangles"
+
+    Examples:
+      | kind     | file     | model         |
+      | an ASCII | scan.ply | scan/scan.3mf |
+      | a binary | bust.ply | bust/bust.3mf |
+
+  Scenario: A PLY file without the end of its header is rejected
+    Given a PLY file "cut.ply" without an end of header
+    And_API the Manyfold Processor service is running
+    When I drop "cut.ply" into the input folder
+    Then the library does not contain "cut"  # [twin: Then_API the job for "cut.ply" fails with reason "ply_header"]
+    Then_API the job for "cut.ply" fails with reason "ply_header"
+
+  Scenario: Every AMF object becomes a 3MF object
+    Given an AMF file "pair.amf" with 2 objects
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "pair.amf" into the input folder
+    Then the library contains "pair/pair.3mf"  # [twin: Then_API the job for "pair.amf" succeeds]
+    And the model "pair/pair.3mf" contains 2 objects  # [twin: Then_API the package of the job for "pair.amf" has 2 objects]
+    Then_API the package of the job for "pair.amf" has 2 objects
+
+  Scenario: An AMF triangle referencing a missing vertex is rejected
+    Given an AMF file "gap.amf" whose last triangle references vertex 8
+    And_API the Manyfold Processor service is running
+    When I drop "gap.amf" into the input folder
+    Then the library does not contain "gap"  # [twin: Then_API the job for "gap.amf" fails with reason "amf_bad_index"]
+    Then_API the job for "gap.amf" fails with reason "amf_bad_index"
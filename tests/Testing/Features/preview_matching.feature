@@ -0,0 +1,49 @@
+Feature: Preview Matching
+  As a maker
+  I want the render that belongs to a model picked as its preview
+  So that Manyfold shows the right picture for every model.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Architecture: architectural_guidelines]
+
+  Scenario: A loose image sharing a name token is the preview
+    Given a binary STL file "car_v1.stl"
+    And an image "car_preview.png"
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "car_v1.stl" and "car_preview.png" into the input folder
+    Then the library contains "car-v1/car-v1.png"  # [twin: Then_API the job for "car_v1.stl" succeeds]
+    And the datapackage of "car-v1" lists "car-v1.png" as its first image
+    Then_API the job log for "car_v1.stl" mentions "score 30"
+
+  Scenario: An unrelated loose image is neither a preview nor a job
+    Given a binary STL file "cube.stl"
+    And an image "boat.png"
+    And_API the Manyfold Processor service is running
+    When I drop "cube.stl" and "boat.png" into the input folder
+    Then the library contains "cube/cube.3mf"  # [twin: Then_API the job for "cube.stl" succeeds]
+    And the datapackage of "cube" lists no images
+    Then_API no job is created for "boat.png"
+
+  Scenario: The only image of a folder is its preview
+    Given a folder "Dragon" with the files:
+      | file       |
+      | dragon.stl |
+      | photo.png  |
+    And_API the Manyfold Processor service is running
+    When I drop "Dragon" into the input folder
+    Then the library contains "dragon/dragon.3mf"  # [twin: Then_API the job for "Dragon" succeeds]
+    And the datapackage of "dragon" lists "photo.png" as its first image
+    Then_API the job log for "Dragon" mentions "only image"
+
+  Scenario: The best scoring image of a folder comes first
+    Given a folder "Knight" with the files:
+      | file                        |
+      | knight.stl                  |
+      | knight_render.png           |
+      | knight.png                  |
+      | renders/knight_painted.png  |
+    And_API the Manyfold Processor service is running
+    When I drop "Knight" into the input folder
+    Then the library contains "knight/knight.3mf"  # [twin: Then_API the job for "Knight" succeeds]
+    And the datapackage of "knight" lists "knight.png" as its first image
+    Then_API the job log for "Knight" mentions "same name"
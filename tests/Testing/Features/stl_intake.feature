@@ -0,0 +1,43 @@
+Feature: STL Intake
+  As a maker
+  I want STL files dropped into the input folder to become 3MF models
+  So that Manyfold shows them ready to slice.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Specification: stl_specification]
+
+  Scenario: A binary STL becomes a 3MF model
+    Given a binary STL file "cube.stl"
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "cube.stl" into the input folder
+    Then the library contains "cube/cube.3mf"  # [twin: Then_API the job for "cube.stl" succeeds]
+    And the model "cube/cube.3mf" contains 1 object  # [twin: Then_API the package of the job for "cube.stl" has 1 object]
+    Then_API the job for "cube.stl" succeeds
+    And_API the package of the job for "cube.stl" has 1 object
+
+  Scenario Outline: An ASCII STL with a non-ASCII solid name is read as text
+    Given an ASCII STL file "<file>" with the <encoding> solid name "<solid>"
+    And_API the Manyfold Processor service is running
+    When I drop "<file>" into the input folder
+    Then the library contains "<model>"  # [twin: Then_API the job for "<file>" succeeds]
+    And the model "<model>" contains 1 object
+    Then_API the job log for "<file>" mentions "12 triangles"
+
+    Examples:
+      | file     | encoding | solid        | model         |
+      | gear.stl | UTF-8    | Zahnrad groß | gear/gear.3mf |
+      | rad.stl  | Latin-1  | Würfel       | rad/rad.3mf   |
+
+  Scenario: A truncated binary STL is rejected
+    Given a binary STL file "broken.stl" cut off after 5 triangles
+    And_API the Manyfold Processor service is running
+    When I drop "broken.stl" into the input folder
+    Then the library does not contain "broken"  # [twin: Then_API the job for "broken.stl" fails with reason "stl_truncated"]
+    Then_API the job for "broken.stl" fails with reason "stl_truncated"
+
+  Scenario: A file too short for an STL header is rejected
+    Given a file "tiny.stl" of 20 bytes
+    And_API the Manyfold Processor service is running
+    When I drop "tiny.stl" into the input folder
+    Then_API the job for "tiny.stl" fails with reason "stl_too_short"
+    And_API the job log for "tiny.stl" mentions "20 bytes"
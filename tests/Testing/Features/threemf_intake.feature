@@ -0,0 +1,24 @@
+Feature: 3MF Intake
+  As a maker
+  I want ready-made 3MF projects filed with their metadata
+  So that Manyfold shows who made a model and under which terms.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Specification: 3mf_specification]
+
+  Scenario: A Bambu Studio project keeps its metadata and plate renders
+    Given a Bambu Studio project "dragon.3mf" titled "Sleeping Dragon" under the license "CC-BY-NC-4.0"
+    And_API the Manyfold Processor service is running
+    When I drop "dragon.3mf" into the input folder
+    Then the library contains "dragon/dragon-plate-1.png"  # [twin: Then_API the job log for "dragon.3mf" mentions "1 plate(s)"]
+    And the datapackage of "dragon" has the title "Sleeping Dragon"
+    And the datapackage of "dragon" keeps the license "CC-BY-NC-4.0"
+    Then_API the job log for "dragon.3mf" mentions "1 plate(s)"
+
+  Scenario: An oversized plate render is not extracted
+    Given a Bambu Studio project "bomb.3mf" whose plate render is 20 MB
+    And_API the Manyfold Processor service is running
+    When I drop "bomb.3mf" into the input folder
+    Then the library contains "bomb/bomb.3mf"  # [twin: Then_API the job for "bomb.3mf" succeeds]
+    And the library does not contain "bomb/bomb-plate-1.png"
+    Then_API the job log for "bomb.3mf" mentions "Skipped plate render"
@@ -0,0 +1,45 @@
+Feature: 3MF Output
+  As a maker
+  I want the generated 3MF packages to open in every slicer
+  So that I can print a model straight from Manyfold.
+
+  # [Testing Strategy: testing_philosophy]
+  # [Specification: 3mf_specification]
+  # ZIP64 records are only written past 4 GiB or 65535 entries, which is out
+  # of reach of a behaviour test.
+
+  Scenario: Packages are deflated and streamed with data descriptors
+    Given a binary STL file "cube.stl"
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "cube.stl" into the input folder
+    Then the library contains "cube/cube.3mf"  # [twin: Then_API the job for "cube.stl" succeeds]
+    And the model "cube/cube.3mf" stores its 3D model deflated in a streamed entry
+    And the model "cube/cube.3mf" can be read by a standard ZIP reader
+    Then_API the package of the job for "cube.stl" has 1 object
+
+  Scenario: Compression level 0 stores entries uncompressed
+    Given the processor setting "ZIP_COMPRESSION_LEVEL" is "0"
+    And a binary STL file "cube.stl"
+    And_API the Manyfold Processor service is running
+    When I drop "cube.stl" into the input folder
+    Then the library contains "cube/cube.3mf"  # [twin: Then_API the job for "cube.stl" succeeds]
+    And the model "cube/cube.3mf" stores its 3D model uncompressed
+    And the model "cube/cube.3mf" can be read by a standard ZIP reader
+
+  Scenario: A matching preview image is embedded as the thumbnail
+    Given a binary STL file "car_v1.stl"
+    And an image "car_preview.png"
+    And the Manyfold Processor service is running  # [twin: Given_API the Manyfold Processor service is running]
+    When I drop "car_v1.stl" and "car_preview.png" into the input folder
+    Then the library contains "car-v1/car-v1.3mf"  # [twin: Then_API the job for "car_v1.stl" succeeds]
+    And the model "car-v1/car-v1.3mf" has an embedded thumbnail  # [twin: Then_API the package of the job for "car_v1.stl" has a thumbnail]
+    Then_API the package of the job for "car_v1.stl" has a thumbnail
+
+  Scenario: A preview that is not a PNG or JPEG is not embedded
+    Given a binary STL file "ship.stl"
+    And a file "ship_render.png" that is not really an image
+    And_API the Manyfold Processor service is running
+    When I drop "ship.stl" and "ship_render.png" into the input folder
+    Then the library contains "ship/ship.3mf"  # [twin: Then_API the job for "ship.stl" succeeds]
+    And the model "ship/ship.3mf" has no embedded thumbnail
+    Then_API the job log for "ship.stl" mentions "not a PNG or JPEG image"
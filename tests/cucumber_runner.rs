@@ -15,11 +15,18 @@ mod steps;
 #[allow(unused_imports)]
 use steps::{given_api_steps, given_steps, then_api_steps, then_steps, when_api_steps, when_steps};
 
+use steps::parser::LayeredParser;
 use steps::world::DashboardWorld;
 
+/// Every scenario runs its own processor; keep a single-CPU runner responsive.
+const MAX_CONCURRENT_SCENARIOS: usize = 4;
+
 #[tokio::main]
 async fn main() {
-    DashboardWorld::cucumber()
-        .run("tests/Testing/Features")
+    DashboardWorld::cucumber::<&str>()
+        .with_parser(LayeredParser)
+        .max_concurrent_scenarios(MAX_CONCURRENT_SCENARIOS)
+        .fail_on_skipped()
+        .run_and_exit("tests/Testing/Features")
         .await;
 }
@@ -0,0 +1,352 @@
+//! Input files generated on the fly: meshes of 10 mm cubes in every supported
+//! format, a tiny PNG, and archives built with the crate's own dependencies.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+pub type Triangle = [[f32; 3]; 3];
+
+const CORNERS: [[f32; 3]; 8] = [
+    [0.0, 0.0, 0.0],
+    [10.0, 0.0, 0.0],
+    [10.0, 10.0, 0.0],
+    [0.0, 10.0, 0.0],
+    [0.0, 0.0, 10.0],
+    [10.0, 0.0, 10.0],
+    [10.0, 10.0, 10.0],
+    [0.0, 10.0, 10.0],
+];
+
+/// Outward-facing triangles of the cube, as indices into [`CORNERS`].
+const FACES: [[usize; 3]; 12] = [
+    [0, 2, 1],
+    [0, 3, 2],
+    [4, 5, 6],
+    [4, 6, 7],
+    [0, 1, 5],
+    [0, 5, 4],
+    [1, 2, 6],
+    [1, 6, 5],
+    [2, 3, 7],
+    [2, 7, 6],
+    [3, 0, 4],
+    [3, 4, 7],
+];
+
+/// A 1x1 RGB PNG.
+pub const PNG: &[u8] = &[
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53,
+    0xDE, 0x00, 0x00, 0x00, 0x0C, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0x60, 0x60, 0x60, 0x00,
+    0x00, 0x00, 0x04, 0x00, 0x01, 0xF6, 0x17, 0x38, 0x55, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4E,
+    0x44, 0xAE, 0x42, 0x60, 0x82,
+];
+
+/// The cube moved `offset` mm along X.
+fn corners(offset: f32) -> impl Iterator<Item = [f32; 3]> {
+    CORNERS.into_iter().map(move |[x, y, z]| [x + offset, y, z])
+}
+
+pub fn cube() -> Vec<Triangle> {
+    FACES
+        .iter()
+        .map(|face| face.map(|corner| CORNERS[corner]))
+        .collect()
+}
+
+/// `count` thin triangles in a row along X, slow enough to convert that a
+/// job stays running for a while.
+pub fn strip(count: usize) -> Vec<Triangle> {
+    (0..count)
+        .map(|i| {
+            let x = i as f32;
+            [[x, 0.0, 0.0], [x + 1.0, 0.0, 0.0], [x, 1.0, 0.0]]
+        })
+        .collect()
+}
+
+pub fn binary_stl(triangles: &[Triangle]) -> Vec<u8> {
+    let mut out = vec![0u8; 80];
+    out.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+    for triangle in triangles {
+        out.extend_from_slice(&[0u8; 12]);
+        for value in triangle.iter().flatten() {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out.extend_from_slice(&[0, 0]);
+    }
+    out
+}
+
+/// An ASCII STL; `name` is written as raw bytes, in whatever encoding.
+pub fn ascii_stl(name: &[u8], triangles: &[Triangle]) -> Vec<u8> {
+    let mut out = b"solid ".to_vec();
+    out.extend_from_slice(name);
+    out.push(b'\n');
+    for [a, b, c] in triangles {
+        out.extend_from_slice(b"  facet normal 0 0 0\n    outer loop\n");
+        for [x, y, z] in [a, b, c] {
+            out.extend_from_slice(format!("      vertex {} {} {}\n", x, y, z).as_bytes());
+        }
+        out.extend_from_slice(b"    endloop\n  endfacet\n");
+    }
+    out.extend_from_slice(b"endsolid ");
+    out.extend_from_slice(name);
+    out.push(b'\n');
+    out
+}
+
+/// One cube per group, side by side.
+pub fn obj(groups: &[&str]) -> Vec<u8> {
+    let mut out = String::new();
+    for (index, group) in groups.iter().enumerate() {
+        out.push_str(&format!("g {}\n", group));
+        for [x, y, z] in corners(index as f32 * 20.0) {
+            out.push_str(&format!("v {} {} {}\n", x, y, z));
+        }
+        for face in FACES {
+            let [a, b, c] = face.map(|corner| index * 8 + corner + 1);
+            out.push_str(&format!("f {} {} {}\n", a, b, c));
+        }
+    }
+    out.into_bytes()
+}
+
+pub fn ply_ascii() -> Vec<u8> {
+    let mut out = ply_header("ascii");
+    for [x, y, z] in CORNERS {
+        out.push_str(&format!("{} {} {}\n", x, y, z));
+    }
+    for [a, b, c] in FACES {
+        out.push_str(&format!("3 {} {} {}\n", a, b, c));
+    }
+    out.into_bytes()
+}
+
+pub fn ply_binary() -> Vec<u8> {
+    let mut out = ply_header("binary_little_endian").into_bytes();
+    for value in CORNERS.iter().flatten() {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    for face in FACES {
+        out.push(3);
+        for corner in face {
+            out.extend_from_slice(&(corner as i32).to_le_bytes());
+        }
+    }
+    out
+}
+
+fn ply_header(format: &str) -> String {
+    format!(
+        "ply\nformat {} 1.0\nelement vertex 8\nproperty float x\nproperty float y\n\
+         property float z\nelement face 12\nproperty list uchar int vertex_indices\nend_header\n",
+        format
+    )
+}
+
+/// `objects` cubes; with `bad_vertex` the last triangle references it.
+pub fn amf(objects: usize, bad_vertex: Option<usize>) -> Vec<u8> {
+    let mut out =
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<amf unit=\"millimeter\">\n");
+    for index in 0..objects {
+        out.push_str(&format!(
+            "  <object id=\"{}\">\n    <mesh>\n      <vertices>\n",
+            index
+        ));
+        for [x, y, z] in corners(index as f32 * 20.0) {
+            out.push_str(&format!(
+                "        <vertex><coordinates><x>{}</x><y>{}</y><z>{}</z></coordinates></vertex>\n",
+                x, y, z
+            ));
+        }
+        out.push_str("      </vertices>\n      <volume>\n");
+        for (face, [a, b, c]) in FACES.into_iter().enumerate() {
+            let c = match bad_vertex {
+                Some(vertex) if face == FACES.len() - 1 => vertex,
+                _ => c,
+            };
+            out.push_str(&format!(
+                "        <triangle><v1>{}</v1><v2>{}</v2><v3>{}</v3></triangle>\n",
+                a, b, c
+            ));
+        }
+        out.push_str("      </volume>\n    </mesh>\n  </object>\n");
+    }
+    out.push_str("</amf>\n");
+    out.into_bytes()
+}
+
+/// A Bambu Studio project: one cube with core metadata, plate and print
+/// settings, a package thumbnail and a render of plate 1.
+pub fn bambu_project(title: &str, license: &str) -> Vec<u8> {
+    bambu_project_with_plate(title, license, PNG)
+}
+
+/// [`bambu_project`] with the given bytes as the render of plate 1.
+pub fn bambu_project_with_plate(title: &str, license: &str, plate: &[u8]) -> Vec<u8> {
+    let mut mesh = String::from("<vertices>");
+    for [x, y, z] in CORNERS {
+        mesh.push_str(&format!("<vertex x=\"{}\" y=\"{}\" z=\"{}\"/>", x, y, z));
+    }
+    mesh.push_str("</vertices><triangles>");
+    for [a, b, c] in FACES {
+        mesh.push_str(&format!(
+            "<triangle v1=\"{}\" v2=\"{}\" v3=\"{}\"/>",
+            a, b, c
+        ));
+    }
+    mesh.push_str("</triangles>");
+    let model = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <model unit=\"millimeter\" xmlns=\"http://schemas.microsoft.com/3dmanufacturing/core/2015/02\">\
+         <metadata name=\"Application\">BambuStudio-01.09.07.52</metadata>\
+         <metadata name=\"Title\">{}</metadata>\
+         <metadata name=\"Designer\">Maker</metadata>\
+         <metadata name=\"License\">{}</metadata>\
+         <resources><object id=\"1\" type=\"model\"><mesh>{}</mesh></object></resources>\
+         <build><item objectid=\"1\"/></build></model>\n",
+        title, license, mesh
+    );
+    let parts: [(&str, &[u8]); 8] = [
+        (
+            "[Content_Types].xml",
+            b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+              <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+              <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+              <Default Extension=\"model\" ContentType=\"application/vnd.ms-package.3dmanufacturing-3dmodel+xml\"/>\
+              <Default Extension=\"png\" ContentType=\"image/png\"/></Types>\n",
+        ),
+        (
+            "_rels/.rels",
+            b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+              <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+              <Relationship Target=\"/3D/3dmodel.model\" Id=\"rel0\" \
+              Type=\"http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel\"/>\
+              <Relationship Target=\"/Metadata/thumbnail.png\" Id=\"rel1\" \
+              Type=\"http://schemas.openxmlformats.org/package/2006/relationships/metadata/thumbnail\"/>\
+              </Relationships>\n",
+        ),
+        ("3D/3dmodel.model", model.as_bytes()),
+        (
+            "Metadata/model_settings.config",
+            b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<config>\
+              <object id=\"1\"><metadata key=\"name\" value=\"cube\"/></object>\
+              <plate><metadata key=\"plater_id\" value=\"1\"/>\
+              <metadata key=\"thumbnail_file\" value=\"Metadata/plate_1.png\"/>\
+              <model_instance><metadata key=\"object_id\" value=\"1\"/></model_instance>\
+              </plate></config>\n",
+        ),
+        (
+            "Metadata/project_settings.config",
+            br#"{"printer_model": "Bambu Lab X1 Carbon", "nozzle_diameter": ["0.4"], "layer_height": "0.2", "filament_type": ["PLA"]}"#,
+        ),
+        ("Metadata/thumbnail.png", PNG),
+        ("Metadata/plate_1.png", plate),
+        ("Metadata/plate_1_small.png", PNG),
+    ];
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, bytes) in parts {
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .expect("3MF part");
+        zip.write_all(bytes).expect("3MF data");
+    }
+    zip.finish().expect("3MF").into_inner()
+}
+
+/// Contents for a file listed by name only: a cube for mesh extensions, the
+/// PNG for images, a line of text otherwise.
+pub fn by_name(name: &str) -> Vec<u8> {
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "stl" => binary_stl(&cube()),
+        "obj" => obj(&["part"]),
+        "ply" => ply_ascii(),
+        "amf" => amf(1, None),
+        "png" => PNG.to_vec(),
+        _ => format!("{}\n", name).into_bytes(),
+    }
+}
+
+/// Writes `bytes` to `path`, creating parent folders.
+pub fn write(path: &Path, bytes: &[u8]) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("cannot create fixture folder");
+    }
+    fs::write(path, bytes).expect("cannot write fixture");
+}
+
+/// An entry of a generated archive.
+pub enum Entry {
+    File(String, Vec<u8>),
+    Link(String, String),
+}
+
+/// Writes an archive in the format named by the extension of `path`:
+/// `.zip`, `.tar`, `.tar.gz`/`.tgz`, `.tar.xz`/`.txz` or `.tar.zst`/`.tzst`.
+pub fn write_archive(path: &Path, entries: &[Entry]) {
+    let name = path.to_string_lossy().to_ascii_lowercase();
+    let file = File::create(path).expect("cannot create archive");
+    if name.ends_with(".zip") {
+        write_zip(file, entries);
+    } else if name.ends_with(".tar") {
+        write_tar(file, entries);
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        write_tar(encoder, entries).finish().expect("gzip");
+    } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+        write_tar(xz2::write::XzEncoder::new(file, 6), entries)
+            .finish()
+            .expect("xz");
+    } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+        let encoder = zstd::Encoder::new(file, 0).expect("zstd");
+        write_tar(encoder, entries).finish().expect("zstd");
+    } else {
+        panic!("no archive writer for {:?}", path);
+    }
+}
+
+fn write_zip(file: File, entries: &[Entry]) {
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for entry in entries {
+        match entry {
+            Entry::File(name, bytes) => {
+                zip.start_file(name.as_str(), options).expect("zip entry");
+                zip.write_all(bytes).expect("zip data");
+            }
+            Entry::Link(name, target) => {
+                zip.add_symlink(name.as_str(), target.as_str(), options)
+                    .expect("zip link");
+            }
+        }
+    }
+    zip.finish().expect("zip");
+}
+
+fn write_tar<W: Write>(out: W, entries: &[Entry]) -> W {
+    let mut tar = tar::Builder::new(out);
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        match entry {
+            Entry::File(name, bytes) => {
+                header.set_size(bytes.len() as u64);
+                tar.append_data(&mut header, name, bytes.as_slice())
+                    .expect("tar entry");
+            }
+            Entry::Link(name, target) => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                tar.append_link(&mut header, name, target)
+                    .expect("tar link");
+            }
+        }
+    }
+    tar.into_inner().expect("tar")
+}
@@ -1,8 +1,10 @@
 use super::world::DashboardWorld;
 use cucumber::given;
 
+// [twin: Given the Manyfold Processor service is running]
 #[given("_API the Manyfold Processor service is running")]
-async fn service_is_running_api(_world: &mut DashboardWorld) {
-    // API layer: check /health endpoint
-    // TODO: Add HTTP client call
+async fn service_is_running_api(world: &mut DashboardWorld) {
+    // API layer: /api/status reports the engine online
+    let status = world.start_service().await.get_json("/api/status").await;
+    assert_eq!(status["engine_status"], "online");
 }
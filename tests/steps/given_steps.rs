@@ -1,8 +1,295 @@
+use super::fixtures::{self, Entry};
 use super::world::DashboardWorld;
+use cucumber::gherkin::Step;
 use cucumber::given;
 
+/// Triangles of the STL that keeps the processor busy: tens of seconds in a
+/// debug build.
+const BUSY_TRIANGLES: usize = 1_000_000;
+
+// [twin: Given_API the Manyfold Processor service is running]
 #[given("the Manyfold Processor service is running")]
-async fn service_is_running(_world: &mut DashboardWorld) {
-    // UI layer: verify service is up visually
-    // TODO: Add browser automation to check page loads
+async fn service_is_running(world: &mut DashboardWorld) {
+    // UI layer: the dashboard page loads
+    let service = world.start_service().await;
+    let (status, body) = service.try_get("/").await.expect("no dashboard");
+    assert_eq!(status, 200, "dashboard answered {}", status);
+    assert!(body.contains("Manyfold"), "not the dashboard:\n{}", body);
+}
+
+#[given(expr = "the processor is busy converting {string}")]
+async fn busy_processor(world: &mut DashboardWorld, name: String) {
+    let bytes = fixtures::binary_stl(&fixtures::strip(BUSY_TRIANGLES));
+    let source = world.sandbox.input().join(&name);
+    let staged = world.sandbox.staging().join(&name);
+    fixtures::write(&staged, &bytes);
+    std::fs::rename(&staged, &source).expect("cannot drop");
+    world.service().job_in_state(&name, "running").await;
+}
+
+#[given(expr = "the processor setting {string} is {string}")]
+async fn processor_setting(world: &mut DashboardWorld, key: String, value: String) {
+    assert!(
+        world.service.is_none(),
+        "settings must come before the service is started"
+    );
+    world.settings.push((key, value));
+}
+
+#[given(expr = "a binary STL file {string}")]
+async fn binary_stl(world: &mut DashboardWorld, name: String) {
+    let bytes = fixtures::binary_stl(&fixtures::cube());
+    fixtures::write(&world.sandbox.staging().join(name), &bytes);
+}
+
+#[given(expr = "the processor stopped while converting {string}")]
+async fn interrupted_job(world: &mut DashboardWorld, name: String) {
+    // The queue file a killed processor leaves behind, with the input in place.
+    let source = world.sandbox.input().join(&name);
+    std::fs::rename(world.sandbox.staging().join(&name), &source).expect("cannot drop");
+    let modified = std::fs::metadata(&source)
+        .and_then(|meta| meta.modified())
+        .expect("mtime")
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("mtime after 1970")
+        .as_secs();
+    let queue = serde_json::json!({
+        "next_id": 2,
+        "jobs": [{
+            "id": 1,
+            "source": source,
+            "state": "running",
+            "attempts": 1,
+            "created_at": 0,
+            "updated_at": 0,
+            "source_modified": modified,
+        }],
+    });
+    fixtures::write(
+        &world.sandbox.config().join("jobs.json"),
+        queue.to_string().as_bytes(),
+    );
+}
+
+#[given(expr = "the library already holds a file named {string}")]
+async fn library_file(world: &mut DashboardWorld, name: String) {
+    fixtures::write(&world.sandbox.output().join(name), b"not a model folder");
+}
+
+#[given(expr = "the processor left spool files behind")]
+async fn stale_spool(world: &mut DashboardWorld) {
+    // What a conversion killed while streaming a large STL leaves on disk.
+    let spool = world.sandbox.config().join("spool");
+    fixtures::write(&spool.join("mesh-1-0.vertices"), &[0; 120]);
+    fixtures::write(&spool.join("mesh-1-0.triangles"), &[0; 48]);
+}
+
+#[given(expr = "a binary STL file {string} cut off after {int} triangles")]
+async fn truncated_stl(world: &mut DashboardWorld, name: String, triangles: usize) {
+    let bytes = fixtures::binary_stl(&fixtures::cube());
+    fixtures::write(
+        &world.sandbox.staging().join(name),
+        &bytes[..84 + 50 * triangles],
+    );
+}
+
+#[given(expr = "an ASCII STL file {string} with the {word} solid name {string}")]
+async fn ascii_stl(world: &mut DashboardWorld, name: String, encoding: String, solid: String) {
+    let solid: Vec<u8> = match encoding.as_str() {
+        "UTF-8" => solid.into_bytes(),
+        "Latin-1" => solid
+            .chars()
+            .map(|c| u8::try_from(c as u32).expect("not a Latin-1 character"))
+            .collect(),
+        other => panic!("unknown encoding {:?}", other),
+    };
+    let bytes = fixtures::ascii_stl(&solid, &fixtures::cube());
+    fixtures::write(&world.sandbox.staging().join(name), &bytes);
+}
+
+#[given(expr = "a file {string} of {int} bytes")]
+async fn sized_file(world: &mut DashboardWorld, name: String, size: usize) {
+    fixtures::write(&world.sandbox.staging().join(name), &vec![b's'; size]);
+}
+
+#[given(expr = "an OBJ file {string} with the groups {string} and {string}")]
+async fn obj_groups(world: &mut DashboardWorld, name: String, first: String, second: String) {
+    let bytes = fixtures::obj(&[&first, &second]);
+    fixtures::write(&world.sandbox.staging().join(name), &bytes);
+}
+
+#[given(expr = "an OBJ file {string} whose last face references vertex {int}")]
+async fn obj_bad_index(world: &mut DashboardWorld, name: String, vertex: i64) {
+    let mut bytes = fixtures::obj(&["part"]);
+    bytes.extend_from_slice(format!("f 1 2 {}\n", vertex).as_bytes());
+    fixtures::write(&world.sandbox.staging().join(name), &bytes);
+}
+
+#[given(expr = "an OBJ file {string} with the vertex {string}")]
+async fn obj_vertex(world: &mut DashboardWorld, name: String, vertex: String) {
+    let mut bytes = format!("v {}\n", vertex).into_bytes();
+    bytes.extend(fixtures::obj(&["part"]));
+    fixtures::write(&world.sandbox.staging().join(name), &bytes);
+}
+
+#[given(expr = "an OBJ file {string} using the material library {string}")]
+async fn obj_mtllib(world: &mut DashboardWorld, name: String, library: String) {
+    let mut bytes = format!("mtllib {}\nusemtl paint\n", library).into_bytes();
+    bytes.extend(fixtures::obj(&["part"]));
+    fixtures::write(&world.sandbox.staging().join(name), &bytes);
+}
+
+#[given(expr = "an ASCII PLY file {string}")]
+async fn ply_ascii(world: &mut DashboardWorld, name: String) {
+    fixtures::write(&world.sandbox.staging().join(name), &fixtures::ply_ascii());
+}
+
+#[given(expr = "a binary PLY file {string}")]
+async fn ply_binary(world: &mut DashboardWorld, name: String) {
+    fixtures::write(&world.sandbox.staging().join(name), &fixtures::ply_binary());
+}
+
+#[given(expr = "a PLY file {string} without an end of header")]
+async fn ply_no_header_end(world: &mut DashboardWorld, name: String) {
+    let text = String::from_utf8(fixtures::ply_ascii()).expect("ASCII PLY");
+    let text = text.replace("end_header\n", "");
+    fixtures::write(&world.sandbox.staging().join(name), text.as_bytes());
+}
+
+#[given(expr = "an AMF file {string} with {int} objects")]
+async fn amf_objects(world: &mut DashboardWorld, name: String, objects: usize) {
+    fixtures::write(
+        &world.sandbox.staging().join(name),
+        &fixtures::amf(objects, None),
+    );
+}
+
+#[given(expr = "an AMF file {string} whose last triangle references vertex {int}")]
+async fn amf_bad_index(world: &mut DashboardWorld, name: String, vertex: usize) {
+    fixtures::write(
+        &world.sandbox.staging().join(name),
+        &fixtures::amf(1, Some(vertex)),
+    );
+}
+
+#[given(expr = "a Bambu Studio project {string} titled {string} under the license {string}")]
+async fn bambu_project(world: &mut DashboardWorld, name: String, title: String, license: String) {
+    fixtures::write(
+        &world.sandbox.staging().join(name),
+        &fixtures::bambu_project(&title, &license),
+    );
+}
+
+#[given(expr = "a Bambu Studio project {string} whose plate render is {int} MB")]
+async fn bambu_large_plate(world: &mut DashboardWorld, name: String, megabytes: usize) {
+    let mut plate = fixtures::PNG.to_vec();
+    plate.resize(megabytes << 20, 0);
+    fixtures::write(
+        &world.sandbox.staging().join(name),
+        &fixtures::bambu_project_with_plate("Bomb", "CC0-1.0", &plate),
+    );
+}
+
+#[given(expr = "an image {string}")]
+async fn image(world: &mut DashboardWorld, name: String) {
+    fixtures::write(&world.sandbox.staging().join(name), fixtures::PNG);
+}
+
+#[given(expr = "a file {string} that is not really an image")]
+async fn fake_image(world: &mut DashboardWorld, name: String) {
+    fixtures::write(&world.sandbox.staging().join(name), b"not an image\n");
+}
+
+#[given(expr = "a folder {string} with the files:")]
+async fn folder(world: &mut DashboardWorld, name: String, step: &Step) {
+    let root = world.sandbox.staging().join(name);
+    for file in table_column(step) {
+        fixtures::write(&root.join(&file), &fixtures::by_name(&file));
+    }
+}
+
+#[given(expr = "the folder {string} ships a datapackage titled {string}")]
+async fn shipped_datapackage(world: &mut DashboardWorld, name: String, title: String) {
+    let package = serde_json::json!({
+        "name": "shipped",
+        "title": title,
+        "licenses": [{ "name": "CC-BY-4.0" }],
+    });
+    fixtures::write(
+        &world.sandbox.staging().join(name).join("datapackage.json"),
+        package.to_string().as_bytes(),
+    );
+}
+
+#[given(expr = "an archive {string} with the files:")]
+async fn archive(world: &mut DashboardWorld, name: String, step: &Step) {
+    let entries: Vec<Entry> = table_column(step)
+        .into_iter()
+        .map(|file| {
+            let bytes = fixtures::by_name(&file);
+            Entry::File(file, bytes)
+        })
+        .collect();
+    fixtures::write_archive(&world.sandbox.staging().join(name), &entries);
+}
+
+#[given(expr = "an archive {string} containing the archive {string}")]
+async fn nested_archive(world: &mut DashboardWorld, name: String, inner: String) {
+    let staging = world.sandbox.staging();
+    let bytes = std::fs::read(staging.join(&inner)).expect("inner archive");
+    fixtures::write_archive(&staging.join(name), &[Entry::File(inner, bytes)]);
+}
+
+#[given(expr = "an archive {string} with an entry named {string}")]
+async fn archive_entry(world: &mut DashboardWorld, name: String, entry: String) {
+    let bytes = fixtures::by_name(&entry);
+    fixtures::write_archive(
+        &world.sandbox.staging().join(name),
+        &[Entry::File(entry, bytes)],
+    );
+}
+
+#[given(expr = "an archive {string} with a link {string} to {string}")]
+async fn archive_link(world: &mut DashboardWorld, name: String, link: String, target: String) {
+    fixtures::write_archive(
+        &world.sandbox.staging().join(name),
+        &[
+            Entry::File("model.stl".to_string(), fixtures::by_name("model.stl")),
+            Entry::Link(link, target),
+        ],
+    );
+}
+
+#[given(expr = "an archive {string} with {int} files")]
+async fn archive_many(world: &mut DashboardWorld, name: String, count: usize) {
+    let entries: Vec<Entry> = (0..count)
+        .map(|index| {
+            let file = format!("part{:05}.stl", index);
+            let bytes = fixtures::by_name(&file);
+            Entry::File(file, bytes)
+        })
+        .collect();
+    fixtures::write_archive(&world.sandbox.staging().join(name), &entries);
+}
+
+#[given(expr = "an archive {string} with a model and {int} MB of zeros")]
+async fn archive_zeros(world: &mut DashboardWorld, name: String, megabytes: usize) {
+    fixtures::write_archive(
+        &world.sandbox.staging().join(name),
+        &[
+            Entry::File("model.stl".to_string(), fixtures::by_name("model.stl")),
+            Entry::File("padding.bin".to_string(), vec![0; megabytes << 20]),
+        ],
+    );
+}
+
+/// Values of a one-column data table, without the header row.
+fn table_column(step: &Step) -> Vec<String> {
+    let table = step.table.as_ref().expect("step needs a data table");
+    table
+        .rows
+        .iter()
+        .skip(1)
+        .map(|row| row[0].clone())
+        .collect()
 }
@@ -2,6 +2,11 @@
 
 pub mod world;
 
+// Test support: the service under test, generated inputs, feature parsing
+pub mod fixtures;
+pub mod parser;
+pub mod service;
+
 // UI Layer Steps
 pub mod given_steps;
 pub mod then_steps;
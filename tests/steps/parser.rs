@@ -0,0 +1,77 @@
+//! Feature file parser for the layered Gherkin of the style guide.
+//!
+//! Gherkin knows no `Given_API` keyword: such lines would be read as
+//! description text and silently skipped. Before parsing, `Given_API x`
+//! (and `When_`/`Then_`/`And_`/`But_API`) is rewritten to `Given _API x`,
+//! which the API step definitions match, and trailing `# [twin: ...]` links
+//! are cut off so they do not become part of the step text.
+
+use cucumber::gherkin::{self, GherkinEnv};
+use cucumber::parser::{Error, Parser};
+use futures::stream;
+use std::path::{Path, PathBuf};
+
+const KEYWORDS: &[&str] = &["Given", "When", "Then", "And", "But"];
+
+#[derive(Debug, Default)]
+pub struct LayeredParser;
+
+impl<I: AsRef<Path>> Parser<I> for LayeredParser {
+    type Cli = cucumber::cli::Empty;
+    type Output = stream::Iter<std::vec::IntoIter<Result<gherkin::Feature, Error>>>;
+
+    fn parse(self, input: I, _cli: Self::Cli) -> Self::Output {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(input);
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("cannot read {:?}: {}", dir, e))
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "feature"))
+            .collect();
+        paths.sort();
+        stream::iter(
+            paths
+                .into_iter()
+                .map(|path| parse_file(&path))
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+fn parse_file(path: &Path) -> Result<gherkin::Feature, Error> {
+    use cucumber::feature::Ext as _;
+
+    let text = std::fs::read_to_string(path).map_err(|source| {
+        Error::from(gherkin::ParseFileError::Reading {
+            path: path.to_path_buf(),
+            source,
+        })
+    })?;
+    let text: String = text.lines().map(|line| rewrite(line) + "\n").collect();
+    let mut feature = gherkin::Feature::parse(text, GherkinEnv::default()).map_err(|source| {
+        Error::from(gherkin::ParseFileError::Parsing {
+            path: path.to_path_buf(),
+            error: None,
+            source,
+        })
+    })?;
+    feature.path = Some(path.to_path_buf());
+    Ok(feature.expand_examples()?)
+}
+
+/// Rewrites one line of a feature file; see the module docs.
+fn rewrite(line: &str) -> String {
+    let line = match line.find("# [twin:") {
+        Some(at) if !line.trim_start().starts_with('#') => line[..at].trim_end(),
+        _ => line,
+    };
+    let indent = line.len() - line.trim_start().len();
+    for keyword in KEYWORDS {
+        if let Some(step) = line[indent..]
+            .strip_prefix(keyword)
+            .and_then(|rest| rest.strip_prefix("_API "))
+        {
+            return format!("{}{} _API {}", &line[..indent], keyword, step);
+        }
+    }
+    line.to_string()
+}
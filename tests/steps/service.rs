@@ -0,0 +1,310 @@
+//! The processor under test: a scratch folder layout and the binary running
+//! against it, reached over its HTTP API.
+
+use serde_json::Value;
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest wait for the service to start or a job to finish. Debug builds on
+/// a single CPU are slow.
+const TIMEOUT: Duration = Duration::from_secs(120);
+const POLL: Duration = Duration::from_millis(250);
+/// Time the watcher gets to notice a drop (settle delay 0, poll every second).
+const PICKUP: Duration = Duration::from_secs(3);
+
+static NEXT_SANDBOX: AtomicU32 = AtomicU32::new(0);
+
+/// Input, output, config and scratch folders of one scenario, plus a staging
+/// folder where fixtures are prepared before they are dropped.
+#[derive(Debug)]
+pub struct Sandbox {
+    pub root: PathBuf,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "manyfold-bdd-{}-{}",
+            std::process::id(),
+            NEXT_SANDBOX.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["input", "output", "config", "work", "staging"] {
+            fs::create_dir_all(root.join(dir)).expect("cannot create sandbox");
+        }
+        Self { root }
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+impl Sandbox {
+    pub fn input(&self) -> PathBuf {
+        self.root.join("input")
+    }
+
+    pub fn output(&self) -> PathBuf {
+        self.root.join("output")
+    }
+
+    pub fn config(&self) -> PathBuf {
+        self.root.join("config")
+    }
+
+    pub fn staging(&self) -> PathBuf {
+        self.root.join("staging")
+    }
+
+    fn log_file(&self) -> PathBuf {
+        self.root.join("processor.log")
+    }
+}
+
+/// A running `manyfold-processor`, stopped on drop.
+#[derive(Debug)]
+pub struct Service {
+    child: Child,
+    log_file: PathBuf,
+    pub base_url: String,
+    client: reqwest::Client,
+}
+
+impl Drop for Service {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Service {
+    /// Starts the binary on a free port and waits until the API answers.
+    pub async fn start(sandbox: &Sandbox, settings: &[(String, String)]) -> Self {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("no free port")
+            .port();
+        let log = fs::File::create(sandbox.log_file()).expect("cannot create log");
+        let child = Command::new(env!("CARGO_BIN_EXE_manyfold-processor"))
+            // The dashboard is served from `static/` relative to the cwd.
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .env("RUST_LOG", "info")
+            .env("INPUT_DIR", sandbox.input())
+            .env("OUTPUT_DIR", sandbox.output())
+            .env("CONFIG_DIR", sandbox.config())
+            .env("WORKING_DIR", sandbox.root.join("work"))
+            .env("WATCHER_SETTLE_SECS", "0")
+            .env("WATCHER_POLL_SECS", "1")
+            .env("WEB_PORT", port.to_string())
+            .envs(settings.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::null())
+            .stdout(log.try_clone().expect("cannot share log"))
+            .stderr(log)
+            .spawn()
+            .expect("cannot start manyfold-processor");
+        let mut service = Self {
+            child,
+            log_file: sandbox.log_file(),
+            base_url: format!("http://127.0.0.1:{}", port),
+            client: reqwest::Client::new(),
+        };
+
+        let started = Instant::now();
+        loop {
+            if let Ok(Some(status)) = service.child.try_wait() {
+                panic!("processor exited with {}:\n{}", status, service.log());
+            }
+            if service.try_get("/api/status").await.is_some() {
+                return service;
+            }
+            if started.elapsed() > TIMEOUT {
+                panic!("processor did not start:\n{}", service.log());
+            }
+            tokio::time::sleep(POLL).await;
+        }
+    }
+
+    /// Status code and body of `GET path`, `None` if nothing answers.
+    pub async fn try_get(&self, path: &str) -> Option<(u16, String)> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .send()
+            .await
+            .ok()?;
+        let status = response.status().as_u16();
+        Some((status, response.text().await.ok()?))
+    }
+
+    /// Status code and body of a `POST` or `DELETE` to `path`.
+    pub async fn send(&self, method: reqwest::Method, path: &str) -> (u16, String) {
+        let response = self
+            .client
+            .request(method.clone(), format!("{}{}", self.base_url, path))
+            .send()
+            .await
+            .unwrap_or_else(|e| panic!("{} {} failed: {}", method, path, e));
+        let status = response.status().as_u16();
+        (status, response.text().await.unwrap_or_default())
+    }
+
+    /// `GET path` as JSON; panics unless it succeeds.
+    pub async fn get_json(&self, path: &str) -> Value {
+        match self.try_get(path).await {
+            Some((200, body)) => serde_json::from_str(&body).expect("invalid JSON"),
+            other => panic!("GET {} answered {:?}", path, other),
+        }
+    }
+
+    /// Summaries of all jobs whose source is the top-level item `name`.
+    pub async fn jobs_for(&self, name: &str) -> Vec<Value> {
+        let jobs = self.get_json("/api/jobs").await;
+        jobs.as_array()
+            .expect("job list")
+            .iter()
+            .filter(|job| {
+                job["source"]
+                    .as_str()
+                    .and_then(|source| Path::new(source).file_name())
+                    .is_some_and(|file| file.to_string_lossy() == name)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Waits until the job of `name` has finished and returns it in full.
+    pub async fn finished_job(&self, name: &str) -> Value {
+        let started = Instant::now();
+        loop {
+            let finished = self.jobs_for(name).await.into_iter().find(|job| {
+                matches!(
+                    job["state"].as_str(),
+                    Some("succeeded" | "failed" | "cancelled")
+                )
+            });
+            if let Some(job) = finished {
+                return self.get_json(&format!("/api/jobs/{}", job["id"])).await;
+            }
+            if started.elapsed() > TIMEOUT {
+                panic!("no finished job for {:?}:\n{}", name, self.log());
+            }
+            tokio::time::sleep(POLL).await;
+        }
+    }
+
+    /// Subscribes to the Server-Sent Events at `path` and collects their JSON
+    /// payloads in the background.
+    pub async fn follow(&self, path: &str) -> EventStream {
+        let mut response = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .send()
+            .await
+            .unwrap_or_else(|e| panic!("GET {} failed: {}", path, e));
+        assert_eq!(response.status(), 200, "GET {}", path);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&events);
+        let task = tokio::spawn(async move {
+            let mut text = String::new();
+            while let Ok(Some(chunk)) = response.chunk().await {
+                text.push_str(&String::from_utf8_lossy(&chunk));
+                // Messages end with a blank line; keep-alives carry no data.
+                while let Some(end) = text.find("\n\n") {
+                    let message: String = text.drain(..end + 2).collect();
+                    for data in message.lines().filter_map(|l| l.strip_prefix("data:")) {
+                        if let Ok(event) = serde_json::from_str(data.trim()) {
+                            received.lock().expect("events").push(event);
+                        }
+                    }
+                }
+            }
+        });
+        EventStream { events, task }
+    }
+
+    /// Waits until the job of `name` is in `state` and returns its summary.
+    pub async fn job_in_state(&self, name: &str, state: &str) -> Value {
+        let started = Instant::now();
+        loop {
+            let jobs = self.jobs_for(name).await;
+            if let Some(job) = jobs.into_iter().find(|job| job["state"] == state) {
+                return job;
+            }
+            if started.elapsed() > TIMEOUT {
+                panic!("no {} job for {:?}:\n{}", state, name, self.log());
+            }
+            tokio::time::sleep(POLL).await;
+        }
+    }
+
+    /// Gives the watcher time to pick up drops, then waits for an empty queue.
+    pub async fn wait_until_idle(&self) {
+        tokio::time::sleep(PICKUP).await;
+        let started = Instant::now();
+        while self.get_json("/api/status").await["queue_count"] != 0 {
+            if started.elapsed() > TIMEOUT {
+                panic!("queue did not drain:\n{}", self.log());
+            }
+            tokio::time::sleep(POLL).await;
+        }
+    }
+
+    /// Whether any job was created for the top-level item `name`.
+    pub async fn has_job_for(&self, name: &str) -> bool {
+        !self.jobs_for(name).await.is_empty()
+    }
+
+    /// Everything the processor logged so far, for failure messages.
+    pub fn log(&self) -> String {
+        fs::read_to_string(&self.log_file).unwrap_or_default()
+    }
+}
+
+/// Events received from `/api/events` so far; the subscription ends on drop.
+#[derive(Debug)]
+pub struct EventStream {
+    events: Arc<Mutex<Vec<Value>>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl EventStream {
+    /// Waits until an event matches `wanted` and returns all events so far.
+    pub async fn until(&self, wanted: impl Fn(&Value) -> bool) -> Vec<Value> {
+        let started = Instant::now();
+        loop {
+            let events = self.events.lock().expect("events").clone();
+            if events.iter().any(&wanted) {
+                return events;
+            }
+            if started.elapsed() > TIMEOUT {
+                panic!("expected event not streamed, got {:?}", events);
+            }
+            tokio::time::sleep(POLL).await;
+        }
+    }
+}
+
+/// Job log messages joined into one string per line.
+pub fn job_log(job: &Value) -> String {
+    job["log"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry["message"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
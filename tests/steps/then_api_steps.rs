@@ -1,8 +1,156 @@
+use super::service::job_log;
 use super::world::DashboardWorld;
 use cucumber::then;
+use serde_json::Value;
+use std::time::{Duration, Instant};
 
+/// Longest wait for the watcher to create a job.
+const JOB_TIMEOUT: Duration = Duration::from_secs(60);
+
+// [twin: Then I should receive a successful visual response on port 8080]
 #[then("_API I should receive a status code of 200")]
 async fn verify_status_code_api(world: &mut DashboardWorld) {
     // API layer: assert response code
     assert_eq!(world.response_code, 200);
 }
+
+// [twin: Then the library contains {string}]
+#[then(expr = "_API the job for {string} succeeds")]
+async fn job_succeeds(world: &mut DashboardWorld, item: String) {
+    let job = world.service().finished_job(&item).await;
+    assert_eq!(job["state"], "succeeded", "{}", job_log(&job));
+}
+
+// [twin: Then the library does not contain {string}]
+#[then(expr = "_API the job for {string} fails with reason {string}")]
+async fn job_fails(world: &mut DashboardWorld, item: String, reason: String) {
+    let job = world.service().finished_job(&item).await;
+    assert_eq!(job["state"], "failed", "{}", job_log(&job));
+    assert_eq!(job["failure_reason"], reason.as_str(), "{}", job_log(&job));
+}
+
+#[then(expr = "_API the job for {string} fails")]
+async fn job_fails_any(world: &mut DashboardWorld, item: String) {
+    let job = world.service().finished_job(&item).await;
+    assert_eq!(job["state"], "failed", "{}", job_log(&job));
+}
+
+#[then(expr = "_API the job for {string} is cancelled")]
+async fn job_cancelled(world: &mut DashboardWorld, item: String) {
+    let job = world.service().finished_job(&item).await;
+    assert_eq!(job["state"], "cancelled", "{}", job_log(&job));
+}
+
+#[then(expr = "_API the job for {string} is no longer listed")]
+async fn job_deleted(world: &mut DashboardWorld, item: String) {
+    let jobs = world.service().jobs_for(&item).await;
+    assert!(jobs.is_empty(), "still listed: {:?}", jobs);
+}
+
+#[then(expr = "_API the response shows the job as {string}")]
+async fn response_job_state(world: &mut DashboardWorld, state: String) {
+    assert_eq!(world.response_code, 200, "{}", world.response_body);
+    let job: Value = serde_json::from_str(&world.response_body).expect("job JSON");
+    assert_eq!(job["state"], state.as_str(), "{:#}", job);
+}
+
+#[then(expr = "_API the event stream reports job {int} as {string}")]
+async fn event_state(world: &mut DashboardWorld, job: u64, state: String) {
+    let events = world.events.as_ref().expect("no event stream followed");
+    events
+        .until(|e| e["type"] == "state" && e["job_id"] == job && e["state"] == state.as_str())
+        .await;
+}
+
+#[then(expr = "_API the event stream carries only events of job {int}")]
+async fn event_filter(world: &mut DashboardWorld, job: u64) {
+    let events = world.events.as_ref().expect("no event stream followed");
+    let finished = |e: &Value| {
+        e["type"] == "state"
+            && e["job_id"] == job
+            && matches!(e["state"].as_str(), Some("succeeded" | "failed"))
+    };
+    events.until(finished).await;
+    // Let the other job finish too before looking for its events.
+    world.service().wait_until_idle().await;
+    let events = events.until(finished).await;
+    assert!(events.iter().all(|e| e["job_id"] == job), "{:?}", events);
+}
+
+#[then(expr = "_API the job for {string} succeeds on attempt {int}")]
+async fn job_succeeds_on(world: &mut DashboardWorld, item: String, attempt: u32) {
+    let service = world.service();
+    let job = service.finished_job(&item).await;
+    assert_eq!(job["state"], "succeeded", "{}", job_log(&job));
+    assert_eq!(job["attempts"], attempt, "{}", job_log(&job));
+    assert_eq!(service.jobs_for(&item).await.len(), 1, "job was duplicated");
+}
+
+#[then(expr = "_API the job log for {string} mentions {string}")]
+async fn job_log_mentions(world: &mut DashboardWorld, item: String, text: String) {
+    let log = job_log(&world.service().finished_job(&item).await);
+    assert!(log.contains(&text), "{:?} not in:\n{}", text, log);
+}
+
+#[then(expr = "_API no job is created for {string}")]
+async fn no_job(world: &mut DashboardWorld, item: String) {
+    let service = world.service();
+    service.wait_until_idle().await;
+    assert!(
+        !service.has_job_for(&item).await,
+        "a job exists for {:?}",
+        item
+    );
+}
+
+#[then(expr = "_API {int} jobs are created for {string}")]
+async fn jobs_created(world: &mut DashboardWorld, count: usize, item: String) {
+    let service = world.service();
+    let started = Instant::now();
+    while service.jobs_for(&item).await.len() < count {
+        if started.elapsed() > JOB_TIMEOUT {
+            panic!(
+                "fewer than {} jobs for {:?}:\n{}",
+                count,
+                item,
+                service.log()
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    assert_eq!(service.jobs_for(&item).await.len(), count);
+}
+
+#[then(expr = "_API the status reports {int} processed, {int} failed and {int} queued job(s)")]
+async fn status_counts(world: &mut DashboardWorld, processed: u64, failed: u64, queued: u64) {
+    let service = world.service();
+    service.wait_until_idle().await;
+    let status = service.get_json("/api/status").await;
+    assert_eq!(status["processed_count"], processed, "{}", status);
+    assert_eq!(status["failed_count"], failed, "{}", status);
+    assert_eq!(status["queue_count"], queued, "{}", status);
+}
+
+// [twin: Then the model {string} contains {int} object(s)]
+#[then(expr = "_API the package of the job for {string} has {int} object(s)")]
+async fn package_objects(world: &mut DashboardWorld, item: String, count: u64) {
+    let package = job_package(world, &item).await;
+    assert_eq!(package["object_count"], count, "{:#}", package);
+}
+
+// [twin: Then the model {string} has an embedded thumbnail]
+#[then(expr = "_API the package of the job for {string} has a thumbnail")]
+async fn package_thumbnail(world: &mut DashboardWorld, item: String) {
+    let package = job_package(world, &item).await;
+    let thumbnails = package["thumbnails"].as_array().map_or(0, Vec::len);
+    assert!(thumbnails > 0, "{:#}", package);
+}
+
+/// Package details of the first 3MF the (finished) job produced.
+async fn job_package(world: &DashboardWorld, item: &str) -> Value {
+    let service = world.service();
+    let job = service.finished_job(item).await;
+    service
+        .get_json(&format!("/api/jobs/{}/package", job["id"]))
+        .await
+}
@@ -1,8 +1,53 @@
 use super::world::DashboardWorld;
 use cucumber::when;
+use reqwest::Method;
 
+// [twin: When I request the dashboard home page]
 #[when("_API I request the status from the API")]
-async fn request_status_api(_world: &mut DashboardWorld) {
-    // API layer: call /status endpoint
-    // TODO: Add HTTP client call
+async fn request_status_api(world: &mut DashboardWorld) {
+    // API layer: call /api/status
+    let (status, _) = world
+        .service()
+        .try_get("/api/status")
+        .await
+        .expect("no API");
+    world.response_code = status;
+}
+
+#[when(expr = "_API the job for {string} has finished")]
+async fn job_finished_api(world: &mut DashboardWorld, item: String) {
+    world.service().finished_job(&item).await;
+}
+
+#[when(expr = "_API I retry the job for {string}")]
+async fn retry_job_api(world: &mut DashboardWorld, item: String) {
+    let job = world.service().finished_job(&item).await;
+    let path = format!("/api/jobs/{}/retry", job["id"]);
+    (world.response_code, world.response_body) = world.service().send(Method::POST, &path).await;
+}
+
+#[when(expr = "_API I cancel the job for {string}")]
+async fn cancel_job_api(world: &mut DashboardWorld, item: String) {
+    let service = world.service();
+    let job = loop {
+        if let Some(job) = service.jobs_for(&item).await.into_iter().next() {
+            break job;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    };
+    let path = format!("/api/jobs/{}/cancel", job["id"]);
+    (world.response_code, world.response_body) = service.send(Method::POST, &path).await;
+}
+
+#[when(expr = "_API I delete the job for {string}")]
+async fn delete_job_api(world: &mut DashboardWorld, item: String) {
+    let job = world.service().finished_job(&item).await;
+    let path = format!("/api/jobs/{}", job["id"]);
+    (world.response_code, world.response_body) = world.service().send(Method::DELETE, &path).await;
+}
+
+#[when(expr = "_API I follow the events of job {int}")]
+async fn follow_events_api(world: &mut DashboardWorld, job: u64) {
+    let path = format!("/api/events?job={}", job);
+    world.events = Some(world.service().follow(&path).await);
 }
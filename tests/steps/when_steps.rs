@@ -1,8 +1,39 @@
 use super::world::DashboardWorld;
 use cucumber::when;
 
+// [twin: When_API I request the status from the API]
 #[when("I request the dashboard home page")]
-async fn request_dashboard(_world: &mut DashboardWorld) {
-    // UI layer: navigate to dashboard via browser
-    // TODO: Add browser automation
+async fn request_dashboard(world: &mut DashboardWorld) {
+    // UI layer: load the dashboard page
+    let (status, body) = world.service().try_get("/").await.expect("no dashboard");
+    world.response_code = status;
+    world.response_body = body;
+}
+
+#[when(expr = "I drop {string} into the input folder")]
+async fn drop_item(world: &mut DashboardWorld, name: String) {
+    drop_into_input(world, &name);
+}
+
+#[when(expr = "I drop {string} and {string} into the input folder")]
+async fn drop_items(world: &mut DashboardWorld, first: String, second: String) {
+    drop_into_input(world, &first);
+    drop_into_input(world, &second);
+}
+
+#[when(expr = "I change {string} in the input folder")]
+async fn change_file(world: &mut DashboardWorld, path: String) {
+    // Rewritten in place: the folder holding it keeps its modification time.
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    let path = world.sandbox.input().join(path);
+    let bytes = std::fs::read(&path).expect("file to change");
+    std::fs::write(&path, bytes).expect("cannot change file");
+}
+
+/// Moves a prepared file or folder into the watched folder in one rename, as
+/// a finished copy would appear.
+fn drop_into_input(world: &DashboardWorld, name: &str) {
+    let from = world.sandbox.staging().join(name);
+    let to = world.sandbox.input().join(name);
+    std::fs::rename(&from, &to).unwrap_or_else(|e| panic!("cannot drop {:?}: {}", from, e));
 }
@@ -1,6 +1,33 @@
+use super::service::{EventStream, Sandbox, Service};
 use cucumber::World;
 
 #[derive(Debug, Default, World)]
 pub struct DashboardWorld {
     pub response_code: u16,
+    /// Body of the last page requested through the UI layer.
+    pub response_body: String,
+    /// Environment overrides applied when the service is started.
+    pub settings: Vec<(String, String)>,
+    /// Events followed through the API layer.
+    pub events: Option<EventStream>,
+    /// Declared before `sandbox` so the process stops before its folders go.
+    pub service: Option<Service>,
+    pub sandbox: Sandbox,
+}
+
+impl DashboardWorld {
+    /// The running service; steps after "the service is running" only.
+    pub fn service(&self) -> &Service {
+        self.service
+            .as_ref()
+            .expect("the Manyfold Processor service is not running")
+    }
+
+    /// Starts the service once, with the settings given so far.
+    pub async fn start_service(&mut self) -> &Service {
+        if self.service.is_none() {
+            self.service = Some(Service::start(&self.sandbox, &self.settings).await);
+        }
+        self.service()
+    }
 }